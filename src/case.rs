@@ -0,0 +1,222 @@
+use crate::db::{CaseRow, Database};
+use anyhow::{anyhow, Error, Result};
+use chrono::{prelude::Utc, DateTime};
+use serenity::model::id::ChannelId;
+use std::fmt;
+use std::str::FromStr;
+
+/// Returned when something that needs a support case is used in a channel that is not one.
+#[derive(Debug)]
+pub struct NotASupportCase(pub ChannelId);
+
+impl fmt::Display for NotASupportCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<#{}> is not a support case", self.0)
+    }
+}
+
+impl std::error::Error for NotASupportCase {}
+
+/// Turns a case ID as typed by a user, e.g. `#1042`, `case-1042` or an old hex ID like
+/// `case-3fa91c`, into the ID stored in the database.
+pub fn parse_id(input: &str) -> String {
+    input
+        .trim()
+        .trim_start_matches('#')
+        .trim_start_matches("case-")
+        .to_lowercase()
+}
+
+/// Finds the support case living in a channel by its stored thread ID, the name of the thread
+/// is never looked at so renamed threads keep working.
+pub fn resolve(db: &Database, channel_id: ChannelId) -> Result<Case> {
+    match db.case_by_thread(*channel_id.as_u64())? {
+        Some(case) => Ok(case),
+        None => Err(NotASupportCase(channel_id).into()),
+    }
+}
+
+/// A support case as stored in the `support` table.
+pub struct Case {
+    pub id: String,
+    pub owner_id: u64,
+    pub thread_id: u64,
+    pub created_at: DateTime<Utc>,
+    pub language: String,
+    pub title: String,
+    pub status: Status,
+    pub claimed_by: Option<u64>,
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub escalated_at: Option<DateTime<Utc>>,
+}
+
+impl Case {
+    pub fn from_row(row: CaseRow) -> Result<Self> {
+        let (
+            id,
+            owner_id,
+            thread_id,
+            created_at,
+            language,
+            title,
+            status,
+            claimed_by,
+            claimed_at,
+            escalated_at,
+        ) = row;
+
+        Ok(Self {
+            id,
+            owner_id,
+            thread_id: thread_id.parse()?,
+            created_at: created_at.parse()?,
+            language,
+            title,
+            status: status.parse()?,
+            claimed_by,
+            claimed_at: claimed_at.map(|c| c.parse()).transpose()?,
+            escalated_at: escalated_at.map(|e| e.parse()).transpose()?,
+        })
+    }
+}
+
+/// The lifecycle state of a support case, as stored in the `status` column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Open,
+    Called,
+    Answered,
+    Resolved,
+    Closed,
+    Abandoned,
+    Reopened,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::Called => "called",
+            Status::Answered => "answered",
+            Status::Resolved => "resolved",
+            Status::Closed => "closed",
+            Status::Abandoned => "abandoned",
+            Status::Reopened => "reopened",
+        }
+    }
+
+    /// Whether the case is still waiting on someone, i.e. the thread should be open.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            Status::Open | Status::Called | Status::Answered | Status::Reopened
+        )
+    }
+
+    pub fn can_transition_to(&self, next: Status) -> bool {
+        match self {
+            Status::Open | Status::Reopened | Status::Called => matches!(
+                next,
+                Status::Called
+                    | Status::Answered
+                    | Status::Resolved
+                    | Status::Closed
+                    | Status::Abandoned
+            ),
+            Status::Answered => matches!(
+                next,
+                Status::Called | Status::Resolved | Status::Closed | Status::Abandoned
+            ),
+            Status::Resolved | Status::Closed | Status::Abandoned => next == Status::Reopened,
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "open" => Ok(Status::Open),
+            "called" => Ok(Status::Called),
+            "answered" => Ok(Status::Answered),
+            "resolved" => Ok(Status::Resolved),
+            "closed" => Ok(Status::Closed),
+            "abandoned" => Ok(Status::Abandoned),
+            "reopened" => Ok(Status::Reopened),
+            _ => Err(anyhow!("Unknown support case status `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_id, Status};
+
+    const ALL: [Status; 7] = [
+        Status::Open,
+        Status::Called,
+        Status::Answered,
+        Status::Resolved,
+        Status::Closed,
+        Status::Abandoned,
+        Status::Reopened,
+    ];
+
+    #[test]
+    fn parse_id_accepts_every_spelling() {
+        assert_eq!(parse_id("1042"), "1042");
+        assert_eq!(parse_id("#1042"), "1042");
+        assert_eq!(parse_id("case-1042"), "1042");
+        assert_eq!(parse_id("  case-1042 "), "1042");
+        assert_eq!(parse_id("case-3FA91C"), "3fa91c");
+    }
+
+    #[test]
+    fn active_cases_can_be_finished() {
+        for status in ALL.iter().filter(|s| s.is_active()) {
+            for next in [Status::Resolved, Status::Closed, Status::Abandoned] {
+                assert!(status.can_transition_to(next), "{} -> {}", status, next);
+            }
+            assert!(!status.can_transition_to(Status::Reopened), "{}", status);
+            assert!(!status.can_transition_to(Status::Open), "{}", status);
+        }
+    }
+
+    #[test]
+    fn finished_cases_can_only_be_reopened() {
+        for status in ALL.iter().filter(|s| !s.is_active()) {
+            for next in ALL {
+                assert_eq!(
+                    status.can_transition_to(next),
+                    next == Status::Reopened,
+                    "{} -> {}",
+                    status,
+                    next
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn answered_cases_are_not_answered_again() {
+        assert!(Status::Open.can_transition_to(Status::Answered));
+        assert!(Status::Called.can_transition_to(Status::Answered));
+        assert!(Status::Answered.can_transition_to(Status::Called));
+        assert!(!Status::Answered.can_transition_to(Status::Answered));
+    }
+
+    #[test]
+    fn statuses_round_trip() {
+        for status in ALL {
+            assert_eq!(status.as_str().parse::<Status>().unwrap(), status);
+        }
+        assert!("pending".parse::<Status>().is_err());
+    }
+}
@@ -0,0 +1,495 @@
+use super::{
+    support::{current_case, is_helper_or_staff, is_staff},
+    utils, Context,
+};
+use crate::case::{self, Status};
+use anyhow::Result;
+use chrono::{prelude::Utc, DateTime, Duration, NaiveDate, SecondsFormat};
+use serenity::model::user::User;
+
+/// The most notes shown at once, Discord does not allow more fields in an embed.
+const NOTE_LIMIT: usize = 25;
+/// How many support cases are shown on a single page of search results.
+const PAGE_SIZE: usize = 10;
+/// The most support cases a single search returns.
+const SEARCH_LIMIT: u32 = 100;
+
+/// `(id, owner_id, thread_id, created_at, language, title, status)` of a support case.
+type CaseRow = (String, u64, String, String, String, String, String);
+
+/// Turns a `YYYY-MM-DD` date into the timestamp format used in the database.
+fn parse_date(date: &str) -> Result<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")?;
+
+    Ok(DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc))
+}
+
+// ========================================================================================
+//                                  Case Command
+// ========================================================================================
+
+/// Looks up support cases
+///
+/// Looks up support cases. Use one of the subcommands to choose what to do. ```
+/// <<prefix>>case info
+/// <<prefix>>case note add <note> [case]
+/// <<prefix>>case note list [case]
+/// <<prefix>>case note delete <id> [case]
+/// <<prefix>>case search [owner] [status] [language] [title] [from] [to]
+/// ```
+#[poise::command(slash_command)]
+pub async fn case(_ctx: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Case Info Command
+// ========================================================================================
+
+/// Shows information about the current support case
+///
+/// Shows everything known about the support case of the current thread, including who claimed it and when the helpers were called. ```
+/// <<prefix>>case info
+/// ```
+#[poise::command(slash_command)]
+pub async fn info(ctx: Context<'_>) -> Result<()> {
+    let case = match current_case(ctx, "case info").await? {
+        Some(case) => case,
+        None => return Ok(()),
+    };
+    let calls = ctx.data().db.lock().unwrap().case_calls(&case.id)?;
+
+    let opened = format!(
+        "{} ago (<t:{}:f>)",
+        utils::chron::time_diff(case.created_at, Utc::now())?,
+        case.created_at.timestamp()
+    );
+    let claimed_by = match (case.claimed_by, case.claimed_at) {
+        (Some(claimant), Some(claimed_at)) => {
+            format!("<@{}> (<t:{}:R>)", claimant, claimed_at.timestamp())
+        }
+        (Some(claimant), None) => format!("<@{}>", claimant),
+        _ => "Nobody".to_string(),
+    };
+    let mut call_history: Vec<String> = Vec::new();
+
+    for (called_by, called_at) in &calls {
+        call_history.push(format!(
+            "<t:{}:f> by <@{}>",
+            called_at.parse::<DateTime<Utc>>()?.timestamp(),
+            called_by
+        ));
+    }
+
+    poise::send_reply(ctx, |m| {
+        m.embed(|embed| {
+            embed.title(&case.title);
+            embed.color(ctx.data().config.env.default_embed_color);
+            embed.field("Case", format!("case-{}", case.id), true);
+            embed.field("Owner", format!("<@{}>", case.owner_id), true);
+            embed.field(
+                "Status",
+                utils::string::into_titlecase(&mut case.status.to_string()).unwrap_or_default(),
+                true,
+            );
+            embed.field("Language", &case.language, true);
+            embed.field("Claimed By", claimed_by, true);
+            embed.field("Opened", opened, true);
+            if let Some(escalated_at) = case.escalated_at {
+                embed.field(
+                    "Escalated",
+                    format!("<t:{}:f>", escalated_at.timestamp()),
+                    true,
+                );
+            }
+            embed.field(
+                format!("Calls ({})", calls.len()),
+                if call_history.is_empty() {
+                    "The helpers have not been called yet.".to_string()
+                } else {
+                    call_history.join("\n")
+                },
+                false,
+            );
+
+            embed
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Case Note Commands
+// ========================================================================================
+
+/// Private notes on support cases
+///
+/// Leaves notes on support cases that only helpers and staff members can see. Use one of the subcommands to choose what to do. ```
+/// <<prefix>>case note add <note> [case]
+/// <<prefix>>case note list [case]
+/// <<prefix>>case note delete <id> [case]
+/// ```
+#[poise::command(slash_command)]
+pub async fn note(_ctx: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+/// Replies with a message only the user of the command can see.
+async fn reply_private(ctx: Context<'_>, content: &str) -> Result<()> {
+    poise::send_reply(ctx, |m| {
+        m.content(content);
+        m.ephemeral(true)
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Finds the case a note command is about, either the given case or the one of the current
+/// thread. Helpers and staff are the only ones allowed to see notes, so anyone else is refused.
+async fn note_case(ctx: Context<'_>, case: Option<String>) -> Result<Option<String>> {
+    if !is_helper_or_staff(ctx.data(), ctx.author().id) {
+        reply_private(ctx, "Only helpers and staff members can use case notes!").await?;
+        return Ok(None);
+    }
+
+    let case_id = {
+        let db = ctx.data().db.lock().unwrap();
+
+        match case {
+            Some(case) => {
+                let case_id = case::parse_id(&case);
+                db.case_thread(&case_id)?.map(|_| case_id)
+            }
+            None => db
+                .case_by_thread(*ctx.channel_id().as_u64())?
+                .map(|case| case.id),
+        }
+    };
+
+    if case_id.is_none() {
+        reply_private(
+            ctx,
+            "Unable to find that support case. Either give a valid case ID or use the command within a support case.",
+        )
+        .await?;
+    }
+
+    Ok(case_id)
+}
+
+/// Adds a note to a support case
+///
+/// Adds a private note to a support case for the other helpers, like what the user was already told. ```
+/// <<prefix>>case note add <note> [case]
+/// ```
+#[poise::command(slash_command, rename = "add")]
+pub async fn note_add(
+    ctx: Context<'_>,
+    #[description = "The note to leave"] note: String,
+    #[description = "The ID of the support case, defaults to the current one"] case: Option<String>,
+) -> Result<()> {
+    let case_id = match note_case(ctx, case).await? {
+        Some(case_id) => case_id,
+        None => return Ok(()),
+    };
+
+    let note_id =
+        ctx.data()
+            .db
+            .lock()
+            .unwrap()
+            .add_note(&case_id, *ctx.author().id.as_u64(), note.trim())?;
+
+    reply_private(
+        ctx,
+        &format!("Added note {} to support case case-{}.", note_id, case_id),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Lists the notes of a support case
+///
+/// Lists the private notes left on a support case. ```
+/// <<prefix>>case note list [case]
+/// ```
+#[poise::command(slash_command, rename = "list")]
+pub async fn note_list(
+    ctx: Context<'_>,
+    #[description = "The ID of the support case, defaults to the current one"] case: Option<String>,
+) -> Result<()> {
+    let case_id = match note_case(ctx, case).await? {
+        Some(case_id) => case_id,
+        None => return Ok(()),
+    };
+
+    let notes = ctx.data().db.lock().unwrap().case_notes(&case_id)?;
+
+    if notes.is_empty() {
+        reply_private(ctx, &format!("Support case case-{} has no notes.", case_id)).await?;
+        return Ok(());
+    }
+
+    let mut fields: Vec<(String, String, bool)> = Vec::new();
+
+    // the newest notes matter the most when there are too many to show
+    for (note_id, author_id, content, created_at) in notes.iter().rev().take(NOTE_LIMIT).rev() {
+        fields.push((
+            format!("Note {}", note_id),
+            utils::string::truncate(
+                &format!(
+                    "{}\n- <@{}> <t:{}:R>",
+                    content,
+                    author_id,
+                    created_at.parse::<DateTime<Utc>>()?.timestamp()
+                ),
+                1024,
+            )?,
+            false,
+        ));
+    }
+
+    poise::send_reply(ctx, |m| {
+        m.embed(|embed| {
+            embed.title(format!("Notes on case-{}", case_id));
+            embed.color(ctx.data().config.env.default_embed_color);
+            embed.fields(fields);
+            embed.footer(|f| {
+                f.text(format!(
+                    "Showing {} of {} notes",
+                    notes.len().min(NOTE_LIMIT),
+                    notes.len()
+                ))
+            });
+
+            embed
+        });
+        m.ephemeral(true)
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes a note from a support case
+///
+/// Deletes a private note from a support case. Helpers can only delete their own notes, staff members can delete any note. ```
+/// <<prefix>>case note delete <id> [case]
+/// ```
+#[poise::command(slash_command, rename = "delete")]
+pub async fn note_delete(
+    ctx: Context<'_>,
+    #[description = "The ID of the note to delete"] id: i64,
+    #[description = "The ID of the support case, defaults to the current one"] case: Option<String>,
+) -> Result<()> {
+    let case_id = match note_case(ctx, case).await? {
+        Some(case_id) => case_id,
+        None => return Ok(()),
+    };
+
+    let author_id = ctx.data().db.lock().unwrap().note_author(&case_id, id)?;
+
+    match author_id {
+        None => {
+            reply_private(
+                ctx,
+                &format!("Support case case-{} has no note {}.", case_id, id),
+            )
+            .await?;
+        }
+        Some(author_id)
+            if &author_id != ctx.author().id.as_u64() && !is_staff(ctx.data(), ctx.author().id) =>
+        {
+            reply_private(
+                ctx,
+                "Only the author of a note and staff members can delete it!",
+            )
+            .await?;
+        }
+        Some(_) => {
+            ctx.data().db.lock().unwrap().delete_note(&case_id, id)?;
+            reply_private(
+                ctx,
+                &format!("Deleted note {} from support case case-{}.", id, case_id),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Case Search Command
+// ========================================================================================
+
+/// Searches through support cases
+///
+/// Searches through all support cases. Every filter is optional, dates use the `YYYY-MM-DD` format and both dates are inclusive. This command can only be used by staff members. ```
+/// <<prefix>>case search [owner] [status] [language] [title] [from] [to]
+/// ```
+#[poise::command(slash_command)]
+pub async fn search(
+    ctx: Context<'_>,
+    #[description = "Only show support cases opened by this user"] owner: Option<User>,
+    #[description = "Only show support cases with this status, e.g. open or resolved"]
+    status: Option<String>,
+    #[description = "Only show support cases in this language"] language: Option<String>,
+    #[description = "Only show support cases with this text in their title"] title: Option<String>,
+    #[description = "Only show support cases opened on or after this date (YYYY-MM-DD)"]
+    from: Option<String>,
+    #[description = "Only show support cases opened on or before this date (YYYY-MM-DD)"]
+    to: Option<String>,
+) -> Result<()> {
+    if !is_helper_or_staff(ctx.data(), ctx.author().id) {
+        poise::send_reply(ctx, |m| {
+            m.content("Only staff members can search through support cases!")
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let mut filters: Vec<&str> = Vec::new();
+    let mut params: Vec<String> = Vec::new();
+
+    if let Some(owner) = owner {
+        filters.push("owner_id = ?");
+        params.push(owner.id.as_u64().to_string());
+    }
+    if let Some(status) = status {
+        let status = match status.trim().to_lowercase().parse::<Status>() {
+            Ok(status) => status,
+            Err(_) => {
+                poise::send_reply(ctx, |m| {
+                    m.content("That is not a valid status. Valid statuses are open, called, answered, resolved, closed, abandoned and reopened.")
+                })
+                .await?;
+                return Ok(());
+            }
+        };
+
+        filters.push("status = ?");
+        params.push(status.as_str().to_string());
+    }
+    if let Some(language) = language {
+        filters.push("language = ? COLLATE NOCASE");
+        params.push(language.trim().to_string());
+    }
+    if let Some(title) = title {
+        // `%` and `_` in the title are searched for literally
+        filters.push("title LIKE ? ESCAPE '\\'");
+        params.push(format!(
+            "%{}%",
+            title
+                .trim()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        ));
+    }
+
+    let dates = (
+        from.as_deref().map(parse_date).transpose(),
+        to.as_deref().map(parse_date).transpose(),
+    );
+    let (from, to) = match dates {
+        (Ok(from), Ok(to)) => (from, to),
+        _ => {
+            poise::send_reply(ctx, |m| {
+                m.content("Dates must be given in the `YYYY-MM-DD` format.")
+            })
+            .await?;
+            return Ok(());
+        }
+    };
+
+    if let Some(from) = from {
+        filters.push("created_at >= ?");
+        params.push(from.to_rfc3339_opts(SecondsFormat::Millis, true));
+    }
+    if let Some(to) = to {
+        filters.push("created_at < ?");
+        params.push((to + Duration::days(1)).to_rfc3339_opts(SecondsFormat::Millis, true));
+    }
+
+    let query = format!(
+        "SELECT id, owner_id, thread_id, created_at, language, title, status FROM support
+            {} ORDER BY created_at DESC LIMIT {}",
+        if filters.is_empty() {
+            "".to_string()
+        } else {
+            format!("WHERE {}", filters.join(" AND "))
+        },
+        SEARCH_LIMIT
+    );
+
+    let cases: Vec<CaseRow>;
+
+    {
+        let db = ctx.data().db.lock().unwrap();
+        let mut stmt = db.conn.prepare(&query)?;
+
+        cases = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |r| {
+                Ok((
+                    r.get(0)?,
+                    r.get(1)?,
+                    r.get(2)?,
+                    r.get(3)?,
+                    r.get(4)?,
+                    r.get(5)?,
+                    r.get(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<CaseRow>>>()?;
+    }
+
+    if cases.is_empty() {
+        poise::send_reply(ctx, |m| m.content("No support cases match those filters.")).await?;
+        return Ok(());
+    }
+
+    let mut pages: Vec<utils::paginate::Page> = Vec::new();
+
+    for chunk in cases.chunks(PAGE_SIZE) {
+        let mut fields: Vec<(String, String, bool)> = Vec::new();
+
+        for (id, owner_id, thread_id, created_at, language, title, status) in chunk {
+            let created_at = created_at.parse::<DateTime<Utc>>()?;
+
+            fields.push((
+                utils::string::truncate(&format!("case-{}: {}", id, title), 256)?,
+                format!(
+                    "<#{}> | <@{}> | {} | {} | <t:{}:d>",
+                    thread_id,
+                    owner_id,
+                    status,
+                    language,
+                    created_at.timestamp()
+                ),
+                false,
+            ));
+        }
+
+        pages.push(utils::paginate::Page {
+            description: Some(format!(
+                "Found {} support case(s){}",
+                cases.len(),
+                if cases.len() as u32 >= SEARCH_LIMIT {
+                    ", only the most recent ones are shown"
+                } else {
+                    ""
+                }
+            )),
+            fields,
+        });
+    }
+
+    utils::paginate::send(ctx, "Support Case Search", pages).await?;
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use super::{utils, Context};
+use anyhow::Result;
+
+/// How many matches are shown on a single page of search results.
+const PAGE_SIZE: usize = 5;
+/// The most matches a single search returns.
+const SEARCH_LIMIT: u32 = 25;
+
+// ========================================================================================
+//                                  Knowledge Base Command
+// ========================================================================================
+
+/// Searches earlier answers
+///
+/// Searches the knowledge base of resolved support cases. Use one of the subcommands to choose what to do. ```
+/// <<prefix>>kb search <query>
+/// ```
+#[poise::command(slash_command)]
+pub async fn kb(_ctx: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Knowledge Base Search Command
+// ========================================================================================
+
+/// Searches resolved support cases
+///
+/// Searches the title, language, question and accepted solution of every resolved support case and links to the best matches, so earlier answers can be found again. ```
+/// <<prefix>>kb search <query>
+/// ```
+#[poise::command(slash_command)]
+pub async fn search(
+    ctx: Context<'_>,
+    #[description = "What to search for"] query: String,
+) -> Result<()> {
+    let matches = {
+        let db = ctx.data().db.lock().unwrap();
+
+        if db.kb_enabled {
+            Some(db.search_kb(&query, SEARCH_LIMIT)?)
+        } else {
+            None
+        }
+    };
+    let matches = match matches {
+        Some(matches) => matches,
+        None => {
+            poise::send_reply(ctx, |m| {
+                m.content("The knowledge base is not available on this server.")
+            })
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    if matches.is_empty() {
+        poise::send_reply(ctx, |m| {
+            m.content("No resolved support cases match your search.")
+        })
+        .await?;
+
+        return Ok(());
+    }
+
+    let total = matches.len();
+    let mut pages: Vec<utils::paginate::Page> = Vec::new();
+
+    for chunk in matches.chunks(PAGE_SIZE) {
+        let mut fields: Vec<(String, String, bool)> = Vec::new();
+
+        for (id, thread_id, title, language, snippet) in chunk {
+            fields.push((
+                utils::string::truncate(&format!("case-{}: {}", id, title), 256)?,
+                utils::string::truncate(
+                    &format!("<#{}> | {}\n{}", thread_id, language, snippet),
+                    1024,
+                )?,
+                false,
+            ));
+        }
+
+        pages.push(utils::paginate::Page {
+            description: Some(format!(
+                "{} resolved support case(s) matching `{}`",
+                total,
+                query.replace('`', "")
+            )),
+            fields,
+        });
+    }
+
+    utils::paginate::send(ctx, "Knowledge Base", pages).await?;
+
+    Ok(())
+}
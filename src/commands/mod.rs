@@ -1,4 +1,7 @@
+pub mod case;
+pub mod kb;
 pub mod meta;
+pub mod stats;
 pub mod support;
 
 pub use super::*;
@@ -12,9 +15,35 @@ pub fn command_list(
     options.command(meta::help(), |f| f.category("Meta"));
     options.command(meta::source(), |f| f.category("Meta"));
 
+    options.command(support::ask(), |f| f.category("Support"));
     options.command(support::call(), |f| f.category("Support"));
+    options.command(support::claim(), |f| f.category("Support"));
+    options.command(support::unclaim(), |f| f.category("Support"));
     options.command(support::close(), |f| f.category("Support"));
+    options.command(support::reopen(), |f| f.category("Support"));
+    options.command(support::mark_solution(), |f| f.category("Support"));
     options.command(support::case_from_message(), |f| f.category("Support"));
+    options.command(case::case(), |f| {
+        f.category("Support");
+        f.subcommand(case::info(), |f| f);
+        f.subcommand(case::note(), |f| {
+            f.subcommand(case::note_add(), |f| f);
+            f.subcommand(case::note_list(), |f| f);
+            f.subcommand(case::note_delete(), |f| f)
+        });
+        f.subcommand(case::search(), |f| f)
+    });
+
+    options.command(kb::kb(), |f| {
+        f.category("Support");
+        f.subcommand(kb::search(), |f| f)
+    });
+
+    options.command(stats::stats(), |f| {
+        f.category("Stats");
+        f.subcommand(stats::helpers(), |f| f);
+        f.subcommand(stats::support(), |f| f)
+    });
 
     Ok(options)
 }
@@ -23,6 +52,7 @@ pub fn get_category_description(category: &str) -> &str {
     match category {
         "Meta" => "Information about the bot",
         "Support" => "Handles all aspects of support cases",
+        "Stats" => "Statistics about the support team",
         _ => "???",
     }
 }
@@ -0,0 +1,329 @@
+use super::{support::is_helper_or_staff, utils, Context};
+use anyhow::Result;
+use chrono::{prelude::Utc, DateTime, Datelike, Duration, NaiveDate, SecondsFormat};
+use serenity::model::id::UserId;
+use std::collections::{BTreeMap, HashMap};
+
+/// How many entries are shown on a single page of a leaderboard.
+const PAGE_SIZE: usize = 5;
+
+#[derive(Default)]
+struct HelperStats {
+    joined: u32,
+    claimed: u32,
+    response_times: Vec<i64>,
+    rating: Option<(f64, u32)>,
+}
+
+/// The most weeks a support trend can go back.
+const MAX_WEEKS: u32 = 20;
+
+#[derive(Default)]
+struct SupportTimes {
+    cases: u32,
+    first_response: Vec<i64>,
+    resolution: Vec<i64>,
+}
+
+#[derive(Default)]
+struct LanguageStats {
+    total: SupportTimes,
+    weeks: BTreeMap<NaiveDate, SupportTimes>,
+}
+
+impl SupportTimes {
+    fn add(&mut self, first_response: Option<i64>, resolution: Option<i64>) {
+        self.cases += 1;
+        self.first_response.extend(first_response);
+        self.resolution.extend(resolution);
+    }
+}
+
+/// Formats the median and 90th percentile of a set of durations in seconds.
+fn format_spread(values: &[i64]) -> Result<String> {
+    match (
+        utils::stats::median(values)?,
+        utils::stats::percentile(values, 90.0)?,
+    ) {
+        (Some(median), Some(p90)) => Ok(format!(
+            "median {}, p90 {}",
+            format_seconds(median)?,
+            format_seconds(p90)?
+        )),
+        _ => Ok("-".to_string()),
+    }
+}
+
+/// Formats a number of seconds the same way as other durations, e.g. `1h, 5m`.
+fn format_seconds(seconds: i64) -> Result<String> {
+    let formatted = utils::chron::format_duration(Duration::seconds(seconds))?;
+
+    if formatted.is_empty() {
+        return Ok("0s".to_string());
+    }
+
+    Ok(formatted)
+}
+
+// ========================================================================================
+//                                  Stats Command
+// ========================================================================================
+
+/// Shows statistics about the support team
+///
+/// Shows statistics about the support team. Use one of the subcommands to choose which statistics to show. ```
+/// <<prefix>>stats helpers [days]
+/// <<prefix>>stats support [weeks]
+/// ```
+#[poise::command(slash_command)]
+pub async fn stats(_ctx: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Helper Stats Command
+// ========================================================================================
+
+/// Shows a leaderboard of the helpers
+///
+/// Shows how many support cases each helper joined and claimed, their median first response time and their average rating over the given number of days. ```
+/// <<prefix>>stats helpers [days]
+/// ```
+#[poise::command(slash_command)]
+pub async fn helpers(
+    ctx: Context<'_>,
+    #[description = "How many days to look back, defaults to 30"] days: Option<u32>,
+) -> Result<()> {
+    let days = days.unwrap_or(30).max(1);
+    let since =
+        (Utc::now() - Duration::days(days as i64)).to_rfc3339_opts(SecondsFormat::Millis, true);
+    let mut stats: HashMap<u64, HelperStats> = HashMap::new();
+
+    {
+        let db = ctx.data().db.lock().unwrap();
+
+        let mut stmt = db.conn.prepare(
+            "SELECT p.user_id, s.created_at, p.first_message_at FROM support_participants p
+                JOIN support s ON s.id = p.case_id
+                WHERE s.created_at >= ?",
+        )?;
+        let participation = stmt
+            .query_map([&since], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<(u64, String, String)>>>()?;
+
+        for (user_id, created_at, first_message_at) in participation {
+            let entry = stats.entry(user_id).or_default();
+            let response_time =
+                first_message_at.parse::<DateTime<Utc>>()? - created_at.parse::<DateTime<Utc>>()?;
+
+            entry.joined += 1;
+            entry.response_times.push(response_time.num_seconds());
+        }
+
+        let mut stmt = db.conn.prepare(
+            "SELECT claimed_by, COUNT(*) FROM support
+                WHERE claimed_by IS NOT NULL AND created_at >= ?
+                GROUP BY claimed_by",
+        )?;
+        let claims = stmt
+            .query_map([&since], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(u64, u32)>>>()?;
+
+        for (user_id, claimed) in claims {
+            stats.entry(user_id).or_default().claimed = claimed;
+        }
+
+        let mut stmt = db.conn.prepare(
+            "SELECT h.helper_id, AVG(r.rating), COUNT(*) FROM support_rating_helpers h
+                JOIN support_ratings r ON r.case_id = h.case_id
+                JOIN support s ON s.id = h.case_id
+                WHERE s.created_at >= ?
+                GROUP BY h.helper_id",
+        )?;
+        let ratings = stmt
+            .query_map([&since], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<(u64, f64, u32)>>>()?;
+
+        for (user_id, average, count) in ratings {
+            stats.entry(user_id).or_default().rating = Some((average, count));
+        }
+    }
+
+    let mut stats: Vec<(u64, HelperStats)> = stats
+        .into_iter()
+        .filter(|(user_id, _)| is_helper_or_staff(ctx.data(), UserId(*user_id)))
+        .collect();
+    stats.sort_by(|(_, a), (_, b)| {
+        b.joined
+            .cmp(&a.joined)
+            .then_with(|| b.claimed.cmp(&a.claimed))
+    });
+
+    if stats.is_empty() {
+        poise::send_reply(ctx, |m| {
+            m.content(format!(
+                "No support activity by helpers was recorded in the last {} days.",
+                days
+            ))
+        })
+        .await?;
+
+        return Ok(());
+    }
+
+    let mut pages: Vec<utils::paginate::Page> = Vec::new();
+
+    for (page_index, chunk) in stats.chunks(PAGE_SIZE).enumerate() {
+        let mut fields: Vec<(String, String, bool)> = Vec::new();
+
+        for (i, (user_id, helper)) in chunk.iter().enumerate() {
+            let median_response = match utils::stats::median(&helper.response_times)? {
+                Some(seconds) => format_seconds(seconds)?,
+                None => "-".to_string(),
+            };
+            let rating = match helper.rating {
+                Some((average, count)) => format!("{:.2}/5 ({} ratings)", average, count),
+                None => "-".to_string(),
+            };
+
+            fields.push((
+                format!("#{}", page_index * PAGE_SIZE + i + 1),
+                format!(
+                    "<@{}>\nCases joined: {}\nCases claimed: {}\nMedian first response: {}\nAverage rating: {}",
+                    user_id, helper.joined, helper.claimed, median_response, rating
+                ),
+                false,
+            ));
+        }
+
+        pages.push(utils::paginate::Page {
+            description: Some(format!("Support activity over the last {} days", days)),
+            fields,
+        });
+    }
+
+    utils::paginate::send(ctx, "Helper Leaderboard", pages).await?;
+
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Support Stats Command
+// ========================================================================================
+
+/// Shows how fast support cases are handled
+///
+/// Shows the median and 90th percentile time to the first helper response and to resolution, with a weekly trend, for all support cases and for each language over the given number of weeks. ```
+/// <<prefix>>stats support [weeks]
+/// ```
+#[poise::command(slash_command)]
+pub async fn support(
+    ctx: Context<'_>,
+    #[description = "How many weeks to look back, defaults to 8"] weeks: Option<u32>,
+) -> Result<()> {
+    let weeks = weeks.unwrap_or(8).clamp(1, MAX_WEEKS);
+    let since =
+        (Utc::now() - Duration::weeks(weeks as i64)).to_rfc3339_opts(SecondsFormat::Millis, true);
+    let mut all = LanguageStats::default();
+    let mut languages: HashMap<String, LanguageStats> = HashMap::new();
+
+    {
+        let db = ctx.data().db.lock().unwrap();
+
+        let mut stmt = db.conn.prepare(
+            "SELECT language, created_at, first_response_at, resolved_at FROM support
+                WHERE created_at >= ?",
+        )?;
+        let cases = stmt
+            .query_map([&since], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, String, Option<String>, Option<String>)>>>()?;
+
+        for (language, created_at, first_response_at, resolved_at) in cases {
+            let created_at: DateTime<Utc> = created_at.parse()?;
+            let first_response = match first_response_at {
+                Some(at) => Some((at.parse::<DateTime<Utc>>()? - created_at).num_seconds()),
+                None => None,
+            };
+            let resolution = match resolved_at {
+                Some(at) => Some((at.parse::<DateTime<Utc>>()? - created_at).num_seconds()),
+                None => None,
+            };
+            let date = created_at.naive_utc().date();
+            let week = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+
+            for stats in [&mut all, languages.entry(language).or_default()] {
+                stats.total.add(first_response, resolution);
+                stats
+                    .weeks
+                    .entry(week)
+                    .or_default()
+                    .add(first_response, resolution);
+            }
+        }
+    }
+
+    if all.total.cases == 0 {
+        poise::send_reply(ctx, |m| {
+            m.content(format!(
+                "No support cases were opened in the last {} weeks.",
+                weeks
+            ))
+        })
+        .await?;
+
+        return Ok(());
+    }
+
+    let mut languages: Vec<(String, LanguageStats)> = languages.into_iter().collect();
+    languages.sort_by(|(a_name, a), (b_name, b)| {
+        b.total
+            .cases
+            .cmp(&a.total.cases)
+            .then_with(|| a_name.cmp(b_name))
+    });
+
+    let mut pages: Vec<utils::paginate::Page> = Vec::new();
+
+    for (language, stats) in std::iter::once(("All Languages".to_string(), all)).chain(languages) {
+        let mut fields: Vec<(String, String, bool)> = vec![
+            ("Cases".to_string(), stats.total.cases.to_string(), true),
+            (
+                "First Response".to_string(),
+                format_spread(&stats.total.first_response)?,
+                true,
+            ),
+            (
+                "Resolution".to_string(),
+                format_spread(&stats.total.resolution)?,
+                true,
+            ),
+        ];
+
+        for (week, times) in &stats.weeks {
+            fields.push((
+                format!("Week of {}", week.format("%Y-%m-%d")),
+                format!(
+                    "Cases: {}\nFirst response: {}\nResolution: {}",
+                    times.cases,
+                    format_spread(&times.first_response)?,
+                    format_spread(&times.resolution)?
+                ),
+                false,
+            ));
+        }
+
+        pages.push(utils::paginate::Page {
+            description: Some(format!(
+                "**{}**, support cases opened over the last {} weeks",
+                language, weeks
+            )),
+            fields,
+        });
+    }
+
+    utils::paginate::send(ctx, "Support Times", pages).await?;
+
+    Ok(())
+}
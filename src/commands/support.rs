@@ -1,219 +1,1909 @@
-use super::Context;
+use super::{utils, Context, State};
+use crate::{
+    case::{self, Case, NotASupportCase, Status},
+    config::Config,
+    db::Database,
+    transcript,
+};
 use anyhow::Result;
 use chrono::{prelude::Utc, DateTime, Duration, SecondsFormat};
-use serenity::futures::{future, StreamExt};
+use rusqlite::OptionalExtension;
+use serenity::builder::{CreateEmbed, CreateInputText};
+use serenity::collector::{
+    component_interaction_collector::CollectComponentInteraction,
+    modal_interaction_collector::CollectModalInteraction,
+};
+use serenity::http::AttachmentType;
 use serenity::model::{
     channel::{ChannelType, GuildChannel, Message},
-    id::{ChannelId, RoleId},
+    event::MessageUpdateEvent,
+    id::{ChannelId, MessageId, RoleId, UserId},
+    interactions::{
+        message_component::{ButtonStyle, InputTextStyle, MessageComponentInteraction},
+        modal::ModalSubmitInteraction,
+        Interaction, InteractionResponseType,
+    },
+    user::User,
 };
+use serenity::prelude::Context as SerenityContext;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+const INTAKE_BUTTON: &str = "support-intake:";
+const INTAKE_MODAL: &str = "support-intake-modal:";
+const CLAIM_BUTTON: &str = "support-claim:";
+const ASK_MODAL: &str = "support-ask-modal";
+
+/// How many resolved cases are compared to the question of a new case.
+const SIMILAR_CANDIDATES: u32 = 20;
+/// The most similar cases suggested when a case opens.
+const SIMILAR_LIMIT: usize = 3;
+/// How similar an earlier question has to be before it is suggested, from 0 to 1.
+const MIN_SIMILARITY: f64 = 0.3;
+/// The most characters Discord allows across the title, description and fields of an embed.
+const EMBED_LIMIT: usize = 6000;
+/// The characters the fences of a code block add around its tag and content.
+const CODE_FENCES: usize = 8;
+
+// ========================================================================================
+//                                  Permissions
+// ========================================================================================
+
+/// Whether a user is a helper or a staff member.
+pub fn is_helper_or_staff(state: &State, user_id: UserId) -> bool {
+    state
+        .roles
+        .has_role(user_id, RoleId(state.config.env.helper_role_id))
+        || is_staff(state, user_id)
+}
+
+/// Whether a user has the staff role, helpers are not included.
+pub fn is_staff(state: &State, user_id: UserId) -> bool {
+    state
+        .roles
+        .has_role(user_id, RoleId(state.config.env.staff_role_id))
+}
+
+// ========================================================================================
+//                                  Case Resolution
+// ========================================================================================
+
+/// Resolves the support case of the channel a command is used in. When the channel is not a
+/// support case the user is told so and `None` is returned.
+pub async fn current_case(ctx: Context<'_>, command: &str) -> Result<Option<Case>> {
+    let resolved = case::resolve(&ctx.data().db.lock().unwrap(), ctx.channel_id());
+
+    match resolved {
+        Ok(case) => Ok(Some(case)),
+        Err(error) if error.is::<NotASupportCase>() => {
+            poise::send_reply(ctx, |m| {
+                m.content(format!(
+                    "The {} command can only be used within support cases.",
+                    command
+                ))
+            })
+            .await?;
+
+            Ok(None)
+        }
+        Err(error) => Err(error),
+    }
+}
+
 // ========================================================================================
 //                                  Create Support Thread
 // ========================================================================================
 
+/// Opens a support case in a new thread started on `message_id` in the support channel. Every
+/// new support case goes through here, no matter how it was asked for. Returns the case ID and
+/// the thread of the new support case.
+#[allow(clippy::too_many_arguments)]
+async fn start_case(
+    ctx: &SerenityContext,
+    state: &State,
+    owner_id: UserId,
+    message_id: MessageId,
+    title: Option<&str>,
+    question: &str,
+    language: Option<&str>,
+    note: Option<&str>,
+) -> Result<(String, ChannelId)> {
+    let case_id = state.db.lock().unwrap().next_case_id()?;
+    let support_channel = ChannelId(state.config.env.support_channel_id);
+    let thread_name = match title {
+        Some(title) => utils::string::truncate(
+            &format!("[{}] {}", language.unwrap_or("Unknown"), title),
+            100,
+        )?,
+        None => "case-".to_string() + &case_id,
+    };
+
+    let thread = support_channel
+        .create_public_thread(&ctx.http, message_id, |t| {
+            t.name(thread_name);
+            t.auto_archive_duration(1440);
+            t.kind(ChannelType::PublicThread);
+
+            t
+        })
+        .await?;
+
+    {
+        let db = state.db.lock().unwrap();
+
+        db.conn.execute(
+            "INSERT INTO support (id, owner_id, thread_id, created_at, language, title, question) VALUES (:id, :owid, :thid, :creat, :lang, :title, :question)",
+            &[(":id", &case_id),
+            (":owid", &owner_id.as_u64().to_string()),
+            (":thid", &thread.id.as_u64().to_string()),
+            (":creat", &Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+            (":lang", &language.unwrap_or("Unknown").to_string()),
+            (":title", &title.unwrap_or("Unknown").to_string()),
+            (":question", &question.to_string())]
+        )?;
+        db.log_status(&case_id, Status::Open, Some(*owner_id.as_u64()), note)?;
+    }
+
+    Ok((case_id, thread.id))
+}
+
 pub async fn create_new(ctx: Context<'_>, message: Message) -> Result<()> {
-    let uuid: String = Uuid::new_v4().to_string()[..6].to_string();
+    let open_cases = ctx
+        .data()
+        .db
+        .lock()
+        .unwrap()
+        .open_cases(*message.author.id.as_u64())?;
+
+    if open_cases.len() as u32 >= ctx.data().config.env.open_case_limit {
+        return refuse_new_case(ctx, &message, &open_cases).await;
+    }
+
     let support_channel = ChannelId(ctx.data().config.env.support_channel_id);
+    let language = detect_language(&message)?;
+    let (case_id, thread) = start_case(
+        ctx.discord(),
+        ctx.data(),
+        message.author.id,
+        message.id,
+        None,
+        &message.content,
+        language,
+        None,
+    )
+    .await?;
+    // the question stays in the support channel, where messages are not tracked
+    ctx.data().db.lock().unwrap().record_edit(
+        &case_id,
+        *message.id.as_u64(),
+        &message.content,
+        &message
+            .timestamp
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    )?;
+
+    support_channel
+        .send_message(&ctx.discord().http, |m| {
+            m.content("Support case opened.");
+
+            m
+        })
+        .await?;
+
+    thread
+        .send_message(&ctx.discord().http, |m| {
+            let mut content = format!(
+                "<@{}>, welcome to your support case! Please add a short title, the language \
+                you are using and what you have already tried so the helpers know what you need.",
+                message.author.id
+            );
+            if let Some(language) = language {
+                content += &format!(
+                    " It looks like your question is about **{}**, you can correct this \
+                    through the details as well.",
+                    language
+                );
+            }
+            m.content(content);
+            m.components(|c| {
+                c.create_action_row(|ar| {
+                    ar.create_button(|b| {
+                        b.style(ButtonStyle::Primary);
+                        b.label("Add Details");
+                        b.custom_id(format!("{}{}", INTAKE_BUTTON, case_id));
+
+                        b
+                    });
+                    ar.create_button(|b| {
+                        b.style(ButtonStyle::Secondary);
+                        b.label("Claim");
+                        b.custom_id(format!("{}{}", CLAIM_BUTTON, case_id));
+
+                        b
+                    });
+                    ar
+                });
+                c
+            });
+
+            m
+        })
+        .await?;
+
+    // suggestions are a nice extra, they should never stop a case from opening
+    if let Err(error) = suggest_similar(ctx.discord(), ctx.data(), thread, &message.content).await {
+        println!(
+            "Unable to suggest similar cases for case-{}: {:?}",
+            case_id, error
+        );
+    }
+
+    Ok(())
+}
+
+/// Posts up to three earlier resolved cases with a similar question in a new support case, so
+/// the user might find their answer before a helper gets to it.
+async fn suggest_similar(
+    ctx: &SerenityContext,
+    state: &State,
+    thread: ChannelId,
+    question: &str,
+) -> Result<()> {
+    let candidates = state
+        .db
+        .lock()
+        .unwrap()
+        .kb_candidates(question, SIMILAR_CANDIDATES)?;
+
+    let mut similar: Vec<(f64, String, u64, String)> = Vec::new();
+
+    for (id, thread_id, title, earlier) in candidates {
+        let score = utils::string::similarity(question, &format!("{}\n{}", title, earlier))?;
+
+        if score >= MIN_SIMILARITY {
+            similar.push((score, id, thread_id, title));
+        }
+    }
+    similar.sort_by(|(a, ..), (b, ..)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    similar.truncate(SIMILAR_LIMIT);
+
+    if similar.is_empty() {
+        return Ok(());
+    }
+
+    thread
+        .send_message(&ctx.http, |m| {
+            m.embed(|embed| {
+                embed.title("Similar Solved Cases");
+                embed.description(
+                    "These earlier support cases asked something similar, maybe one of them \
+                    already answers your question.",
+                );
+                embed.color(state.config.env.default_embed_color);
+                for (_, id, thread_id, title) in &similar {
+                    embed.field(
+                        utils::string::truncate(&format!("case-{}: {}", id, title), 256)
+                            .unwrap_or_default(),
+                        format!("<#{}>", thread_id),
+                        false,
+                    );
+                }
+
+                embed
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Points a user who is at the open case limit to the cases they already have instead of
+/// opening a new one. The message is deleted if they could be told through a DM, otherwise it
+/// is replied to and marked so the message does not look like a new case.
+async fn refuse_new_case(
+    ctx: Context<'_>,
+    message: &Message,
+    open_cases: &[(String, u64)],
+) -> Result<()> {
+    let content = case_limit_message(open_cases);
+
+    let dm = message
+        .author
+        .direct_message(&ctx.discord().http, |m| m.content(&content))
+        .await;
+
+    if dm.is_ok() && message.delete(&ctx.discord().http).await.is_ok() {
+        return Ok(());
+    }
+    if dm.is_err() {
+        message.reply(&ctx.discord().http, &content).await?;
+    }
+    message.react(&ctx.discord().http, '❌').await?;
+
+    Ok(())
+}
+
+/// Tells a user at the open case limit where their open support cases are.
+fn case_limit_message(open_cases: &[(String, u64)]) -> String {
+    format!(
+        "You already have {} open support case(s), which is the limit. Please continue in {} or \
+        close one of them before opening a new support case.",
+        open_cases.len(),
+        open_cases
+            .iter()
+            .map(|(_, thread_id)| format!("<#{}>", thread_id))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Guesses the language of a support case from the message it was opened with.
+fn detect_language(message: &Message) -> Result<Option<&'static str>> {
+    let filenames: Vec<&str> = message
+        .attachments
+        .iter()
+        .map(|a| a.filename.as_str())
+        .collect();
+
+    utils::language::detect(&message.content, &filenames)
+}
+
+// ========================================================================================
+//                                  Ask Command
+// ========================================================================================
+
+/// Opens a support case through a form.
+///
+/// Opens a form asking for the title, language, error output, expected behaviour and code of your problem, then opens a support case with everything the helpers need to know. You can still open a support case by sending a message in the support channel as well. ```
+/// <<prefix>>ask
+/// ```
+#[poise::command(slash_command)]
+pub async fn ask(ctx: Context<'_>) -> Result<()> {
+    let interaction = match ctx {
+        poise::Context::Application(actx) => actx.interaction,
+        poise::Context::Prefix(_) => {
+            poise::send_reply(ctx, |m| {
+                m.content("The ask command can only be used as a slash command.")
+            })
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let open_cases = ctx
+        .data()
+        .db
+        .lock()
+        .unwrap()
+        .open_cases(*ctx.author().id.as_u64())?;
+
+    if open_cases.len() as u32 >= ctx.data().config.env.open_case_limit {
+        poise::send_reply(ctx, |m| {
+            m.content(case_limit_message(&open_cases));
+            m.ephemeral(true)
+        })
+        .await?;
+        return Ok(());
+    }
+
+    // the form is handled in `handle_interaction`, so writing it out can take as long as needed
+    interaction
+        .create_interaction_response(ctx.discord(), |ir| {
+            ir.kind(InteractionResponseType::Modal);
+            ir.interaction_response_data(|d| {
+                d.custom_id(ASK_MODAL);
+                d.title("Ask for Help");
+                d.components(|c| {
+                    c.create_action_row(|ar| {
+                        ar.create_input_text(|it| title_input(it, None));
+                        ar
+                    });
+                    c.create_action_row(|ar| {
+                        ar.create_input_text(|it| language_input(it, None));
+                        ar
+                    });
+                    c.create_action_row(|ar| {
+                        ar.create_input_text(|it| {
+                            it.custom_id("error");
+                            it.label("Error Output");
+                            it.style(InputTextStyle::Paragraph);
+                            it.placeholder("The full error message, if there is one");
+                            it.max_length(1000);
+                            it.required(false);
+
+                            it
+                        });
+                        ar
+                    });
+                    c.create_action_row(|ar| {
+                        ar.create_input_text(|it| {
+                            it.custom_id("expected");
+                            it.label("Expected Behaviour");
+                            it.style(InputTextStyle::Paragraph);
+                            it.placeholder("What should happen instead?");
+                            it.max_length(1000);
+                            it.required(true);
+
+                            it
+                        });
+                        ar
+                    });
+                    c.create_action_row(|ar| {
+                        ar.create_input_text(|it| {
+                            it.custom_id("code");
+                            it.label("Code");
+                            it.style(InputTextStyle::Paragraph);
+                            it.placeholder("The code that causes the problem");
+                            it.max_length(3900);
+                            it.required(false);
+
+                            it
+                        });
+                        ar
+                    });
+                    c
+                })
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn submit_ask(
+    ctx: &SerenityContext,
+    state: &State,
+    modal: &ModalSubmitInteraction,
+) -> Result<()> {
+    // another case may have been opened while the form was being filled out
+    let open_cases = state
+        .db
+        .lock()
+        .unwrap()
+        .open_cases(*modal.user.id.as_u64())?;
+
+    if open_cases.len() as u32 >= state.config.env.open_case_limit {
+        modal
+            .create_interaction_response(&ctx.http, |ir| {
+                ir.kind(InteractionResponseType::ChannelMessageWithSource);
+                ir.interaction_response_data(|d| {
+                    d.content(case_limit_message(&open_cases));
+                    d.ephemeral(true)
+                })
+            })
+            .await?;
+        return Ok(());
+    }
+
+    // opening the thread can take longer than Discord waits for a response
+    modal
+        .create_interaction_response(&ctx.http, |ir| {
+            ir.kind(InteractionResponseType::DeferredChannelMessageWithSource);
+            ir.interaction_response_data(|d| d.ephemeral(true))
+        })
+        .await?;
+
+    // whatever happens from here on, the deferred response has to be updated
+    let result = open_asked_case(ctx, state, modal).await;
+    modal
+        .edit_original_interaction_response(&ctx.http, |r| match &result {
+            Ok(thread) => r.content(format!(
+                "Your support case has been opened in <#{}>.",
+                thread
+            )),
+            Err(_) => r.content("Something went wrong while opening your support case."),
+        })
+        .await?;
+
+    result.map(|_| ())
+}
+
+/// Opens the support case described in a submitted ask form, returning its thread.
+async fn open_asked_case(
+    ctx: &SerenityContext,
+    state: &State,
+    modal: &ModalSubmitInteraction,
+) -> Result<ChannelId> {
+    let values = utils::interaction::modal_values(modal)?;
+    let title = values.get("title").cloned().unwrap_or_default();
+    let language = values.get("language").cloned().unwrap_or_default();
+    let error = values.get("error").filter(|e| !e.is_empty()).cloned();
+    let expected = values.get("expected").cloned().unwrap_or_default();
+    let code = values.get("code").filter(|c| !c.is_empty()).cloned();
+
+    let support_channel = ChannelId(state.config.env.support_channel_id);
+    let thread_msg = support_channel
+        .send_message(&ctx.http, |m| {
+            m.content(format!(
+                "<@{}> asked for help with **{}**.",
+                modal.user.id, title
+            ));
+            // the title is free text, only the asker may be pinged by it
+            m.allowed_mentions(|am| am.empty_parse().users(vec![modal.user.id]))
+        })
+        .await?;
+
+    let question = vec![Some(expected.as_str()), error.as_deref(), code.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<&str>>()
+        .join("\n\n");
+
+    let (case_id, thread) = start_case(
+        ctx,
+        state,
+        modal.user.id,
+        thread_msg.id,
+        Some(&title),
+        &question,
+        Some(&language),
+        Some("opened through the ask form"),
+    )
+    .await?;
+
+    thread.add_thread_member(&ctx.http, modal.user.id).await?;
+
+    let opening = thread
+        .send_message(&ctx.http, |m| {
+            m.content(format!(
+                "<@{}>, welcome to your support case! A helper will be with you soon.",
+                modal.user.id
+            ));
+            m.embed(|embed| {
+                ask_embed(
+                    embed,
+                    state,
+                    &case_id,
+                    modal.user.id,
+                    &title,
+                    &language,
+                    error.as_deref(),
+                    &expected,
+                    code.as_deref(),
+                )
+            });
+            m.components(|c| {
+                c.create_action_row(|ar| {
+                    ar.create_button(|b| {
+                        b.style(ButtonStyle::Secondary);
+                        b.label("Claim");
+                        b.custom_id(format!("{}{}", CLAIM_BUTTON, case_id));
+
+                        b
+                    });
+                    ar
+                });
+                c
+            });
+
+            m
+        })
+        .await?;
+    opening.pin(&ctx.http).await?;
+
+    if let Err(error) =
+        suggest_similar(ctx, state, thread, &format!("{}\n{}", title, question)).await
+    {
+        println!(
+            "Unable to suggest similar cases for case-{}: {:?}",
+            case_id, error
+        );
+    }
+
+    Ok(thread)
+}
+
+/// Wraps text in a code block, making sure fences inside of it cannot end the block early.
+fn code_block(content: &str, tag: &str, length: usize) -> Result<String> {
+    Ok(format!(
+        "```{}\n{}\n```",
+        tag,
+        utils::string::truncate(&content.replace("```", "``\u{200b}`"), length)?
+    ))
+}
+
+/// The opening embed of a support case asked for through the ask form.
+#[allow(clippy::too_many_arguments)]
+fn ask_embed<'a>(
+    embed: &'a mut CreateEmbed,
+    state: &State,
+    case_id: &str,
+    owner_id: UserId,
+    title: &str,
+    language: &str,
+    error: Option<&str>,
+    expected: &str,
+    code: Option<&str>,
+) -> &'a mut CreateEmbed {
+    // tagging the code block with the language lets Discord highlight it
+    let tag = language.to_lowercase().replace(' ', "");
+
+    let mut fields = vec![
+        ("Case", format!("case-{}", case_id), true),
+        ("Owner", format!("<@{}>", owner_id), true),
+        ("Language", language.to_string(), true),
+        ("Expected Behaviour", expected.to_string(), false),
+    ];
+    if let Some(error) = error {
+        fields.push((
+            "Error Output",
+            code_block(error, "", 1000).unwrap_or_default(),
+            false,
+        ));
+    }
+
+    embed.title(title);
+    embed.color(state.config.env.default_embed_color);
+    if let Some(code) = code {
+        // the code gets whatever is left of the embed limit once everything else is in
+        let used = title.chars().count()
+            + fields
+                .iter()
+                .map(|(name, value, _)| name.chars().count() + value.chars().count())
+                .sum::<usize>();
+        let length = EMBED_LIMIT
+            .saturating_sub(used + tag.chars().count() + CODE_FENCES)
+            .min(4000);
+        embed.description(code_block(code, &tag, length.max(1)).unwrap_or_default());
+    }
+    for (name, value, inline) in fields {
+        embed.field(name, value, inline);
+    }
+
+    embed
+}
+
+/// The title field shared by the ask form and the details form of a support case.
+fn title_input<'a>(it: &'a mut CreateInputText, value: Option<&str>) -> &'a mut CreateInputText {
+    it.custom_id("title");
+    it.label("Title");
+    it.style(InputTextStyle::Short);
+    it.placeholder("A short summary of your problem");
+    it.max_length(80);
+    it.required(true);
+    if let Some(value) = value {
+        it.value(value);
+    }
+
+    it
+}
+
+/// The programming language field shared by the ask form and the details form of a support
+/// case.
+fn language_input<'a>(it: &'a mut CreateInputText, value: Option<&str>) -> &'a mut CreateInputText {
+    it.custom_id("language");
+    it.label("Programming Language");
+    it.style(InputTextStyle::Short);
+    it.placeholder("e.g. Rust, Python, JavaScript");
+    it.max_length(30);
+    it.required(true);
+    if let Some(value) = value {
+        it.value(value);
+    }
+
+    it
+}
+
+// ========================================================================================
+//                                  Component Interactions
+// ========================================================================================
+
+pub async fn handle_interaction(
+    ctx: &SerenityContext,
+    state: &State,
+    interaction: &Interaction,
+) -> Result<()> {
+    match interaction {
+        Interaction::MessageComponent(mci) => {
+            if let Some(case_id) = mci.data.custom_id.strip_prefix(INTAKE_BUTTON) {
+                open_intake(ctx, state, mci, case_id).await?;
+            } else if let Some(case_id) = mci.data.custom_id.strip_prefix(CLAIM_BUTTON) {
+                claim_from_button(ctx, state, mci, case_id).await?;
+            }
+        }
+        Interaction::ModalSubmit(modal) => {
+            if let Some(case_id) = modal.data.custom_id.strip_prefix(INTAKE_MODAL) {
+                submit_intake(ctx, state, modal, case_id).await?;
+            } else if modal.data.custom_id == ASK_MODAL {
+                submit_ask(ctx, state, modal).await?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+async fn reply_ephemeral(
+    ctx: &SerenityContext,
+    mci: &MessageComponentInteraction,
+    content: &str,
+) -> Result<()> {
+    mci.create_interaction_response(&ctx.http, |ir| {
+        ir.kind(InteractionResponseType::ChannelMessageWithSource);
+        ir.interaction_response_data(|d| {
+            d.content(content);
+            d.ephemeral(true)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Case Intake
+// ========================================================================================
+
+async fn open_intake(
+    ctx: &SerenityContext,
+    state: &State,
+    mci: &MessageComponentInteraction,
+    case_id: &str,
+) -> Result<()> {
+    let row: rusqlite::Result<Option<(u64, String, String, Option<String>)>> = state
+        .db
+        .lock()
+        .unwrap()
+        .conn
+        .query_row(
+            "SELECT owner_id, title, language, tried FROM support WHERE id = ?",
+            [case_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .optional();
+    let (owner_id, title, language, tried) = match row {
+        Ok(Some(row)) => row,
+        Ok(None) => {
+            reply_ephemeral(ctx, mci, "This support case could not be found.").await?;
+            return Ok(());
+        }
+        Err(error) => {
+            reply_ephemeral(
+                ctx,
+                mci,
+                "Something went wrong while loading this support case.",
+            )
+            .await?;
+            return Err(error.into());
+        }
+    };
+
+    if mci.user.id.as_u64() != &owner_id && !is_helper_or_staff(state, mci.user.id) {
+        reply_ephemeral(
+            ctx,
+            mci,
+            "Only the owner of this support case can add its details.",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    mci.create_interaction_response(&ctx.http, |ir| {
+        ir.kind(InteractionResponseType::Modal);
+        ir.interaction_response_data(|d| {
+            d.custom_id(format!("{}{}", INTAKE_MODAL, case_id));
+            d.title("Support Case Details");
+            d.components(|c| {
+                c.create_action_row(|ar| {
+                    ar.create_input_text(|it| {
+                        title_input(it, Some(title.as_str()).filter(|t| *t != "Unknown"))
+                    });
+                    ar
+                });
+                c.create_action_row(|ar| {
+                    ar.create_input_text(|it| {
+                        language_input(it, Some(language.as_str()).filter(|l| *l != "Unknown"))
+                    });
+                    ar
+                });
+                c.create_action_row(|ar| {
+                    ar.create_input_text(|it| {
+                        it.custom_id("tried");
+                        it.label("What have you already tried?");
+                        it.style(InputTextStyle::Paragraph);
+                        it.max_length(1000);
+                        it.required(false);
+                        if let Some(tried) = &tried {
+                            it.value(tried);
+                        }
+
+                        it
+                    });
+                    ar
+                });
+                c
+            })
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+async fn submit_intake(
+    ctx: &SerenityContext,
+    state: &State,
+    modal: &ModalSubmitInteraction,
+    case_id: &str,
+) -> Result<()> {
+    let values = utils::interaction::modal_values(modal)?;
+    let title = values.get("title").cloned().unwrap_or_default();
+    let language = values.get("language").cloned().unwrap_or_default();
+    let tried = values.get("tried").filter(|t| !t.is_empty()).cloned();
+
+    let (owner_id, thread_id, summary_message_id): (u64, String, Option<String>) = {
+        let db = state.db.lock().unwrap();
+
+        db.conn.execute(
+            "UPDATE support SET title = :title, language = :lang, tried = :tried WHERE id = :id",
+            rusqlite::named_params! {
+                ":title": &title,
+                ":lang": &language,
+                ":tried": &tried,
+                ":id": case_id,
+            },
+        )?;
+        db.conn.query_row(
+            "SELECT owner_id, thread_id, summary_message_id FROM support WHERE id = ?",
+            [case_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )?
+    };
+    let thread = ChannelId(thread_id.parse()?);
+    let thread_name = utils::string::truncate(&format!("[{}] {}", language, title), 100)?;
+
+    thread
+        .edit_thread(&ctx.http, |t| t.name(thread_name))
+        .await?;
+
+    match summary_message_id {
+        Some(summary_message_id) => {
+            thread
+                .edit_message(&ctx.http, MessageId(summary_message_id.parse()?), |m| {
+                    m.embed(|embed| {
+                        summary_embed(
+                            embed,
+                            state,
+                            case_id,
+                            owner_id,
+                            &title,
+                            &language,
+                            tried.as_deref(),
+                        )
+                    })
+                })
+                .await?;
+        }
+        None => {
+            let summary = thread
+                .send_message(&ctx.http, |m| {
+                    m.embed(|embed| {
+                        summary_embed(
+                            embed,
+                            state,
+                            case_id,
+                            owner_id,
+                            &title,
+                            &language,
+                            tried.as_deref(),
+                        )
+                    })
+                })
+                .await?;
+            summary.pin(&ctx.http).await?;
+
+            state.db.lock().unwrap().conn.execute(
+                "UPDATE support SET summary_message_id = ? WHERE id = ?",
+                [summary.id.as_u64().to_string().as_str(), case_id],
+            )?;
+        }
+    }
+
+    modal
+        .create_interaction_response(&ctx.http, |ir| {
+            ir.kind(InteractionResponseType::ChannelMessageWithSource);
+            ir.interaction_response_data(|d| {
+                d.content("The details of this support case have been updated.");
+                d.ephemeral(true)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn summary_embed<'a>(
+    embed: &'a mut CreateEmbed,
+    state: &State,
+    case_id: &str,
+    owner_id: u64,
+    title: &str,
+    language: &str,
+    tried: Option<&str>,
+) -> &'a mut CreateEmbed {
+    embed.title(title);
+    embed.color(state.config.env.default_embed_color);
+    embed.field("Case", format!("case-{}", case_id), true);
+    embed.field("Owner", format!("<@{}>", owner_id), true);
+    embed.field("Language", language, true);
+    embed.field("Already Tried", tried.unwrap_or("Nothing yet"), false);
+
+    embed
+}
+
+// ========================================================================================
+//                                  Case Activity
+// ========================================================================================
+
+pub fn track_message(state: &State, message: &Message) -> Result<()> {
+    let db = state.db.lock().unwrap();
+    let (case_id, owner_id, status) = match db.case_by_thread(*message.channel_id.as_u64())? {
+        Some(case) => (case.id, case.owner_id, case.status),
+        None => return Ok(()),
+    };
+
+    db.record_activity(&case_id)?;
+    // the first version of every message is kept, there is no message cache to recover it
+    // from once the message is edited
+    db.record_edit(
+        &case_id,
+        *message.id.as_u64(),
+        &message.content,
+        &message
+            .timestamp
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    )?;
+
+    if message.author.id.as_u64() != &owner_id {
+        db.record_participant(&case_id, *message.author.id.as_u64())?;
+    }
+    // only helpers answer a case, bystanders chiming in leave it waiting
+    if message.author.id.as_u64() != &owner_id && is_helper_or_staff(state, message.author.id) {
+        db.record_response(&case_id)?;
+
+        if status != Status::Answered && status.can_transition_to(Status::Answered) {
+            db.set_status(
+                &case_id,
+                Status::Answered,
+                Some(*message.author.id.as_u64()),
+                None,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn track_edit(state: &State, old: Option<&Message>, event: &MessageUpdateEvent) -> Result<()> {
+    // the question of a case is the support channel message its thread was started on, which
+    // shares its ID with the thread
+    let thread_id = if event.channel_id == state.config.env.support_channel_id {
+        *event.id.as_u64()
+    } else {
+        *event.channel_id.as_u64()
+    };
+
+    let db = state.db.lock().unwrap();
+    let case_id = match db.case_by_thread(thread_id)? {
+        Some(case) => case.id,
+        None => return Ok(()),
+    };
+
+    if let Some(old) = old {
+        db.record_edit(
+            &case_id,
+            *old.id.as_u64(),
+            &old.content,
+            &old.edited_timestamp
+                .unwrap_or(old.timestamp)
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+        )?;
+    }
+    if let Some(content) = &event.content {
+        db.record_edit(
+            &case_id,
+            *event.id.as_u64(),
+            content,
+            &Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        )?;
+    }
+
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Call Command
+// ========================================================================================
+
+/// Calls the helpers to your support case.
+///
+/// Calls the helpers to your support case. Helpers who know the language of the case are called, or every helper if nobody does. However, this command cannot be used until some time has passed since the case opened or was last called, and a case can only be called a limited number of times. ```
+/// <<prefix>>call
+/// ```
+#[poise::command(slash_command)]
+pub async fn call(ctx: Context<'_>) -> Result<()> {
+    let thread_id = ctx.channel_id();
+    let (case_id, status, opened_at) = match current_case(ctx, "call").await? {
+        Some(case) => (case.id, case.status, case.created_at),
+        None => return Ok(()),
+    };
+
+    let calls = ctx.data().db.lock().unwrap().case_calls(&case_id)?;
+    let env = &ctx.data().config.env;
+
+    if calls.len() as u32 >= env.call_limit {
+        poise::send_reply(ctx, |m| {
+            m.content(format!(
+                "The helpers have already been called to this support case {} times, which is \
+                the limit. Please wait for someone to respond.",
+                calls.len()
+            ))
+        })
+        .await?;
+
+        return Ok(());
+    }
+
+    let last_call: Option<DateTime<Utc>> = match calls.last() {
+        Some((_, called_at)) => Some(called_at.parse()?),
+        None => None,
+    };
+    let next_call = match last_call {
+        Some(last_call) => (last_call + Duration::from_std(env.call_cooldown)?)
+            .max(opened_at + Duration::from_std(env.call_min_case_age)?),
+        None => opened_at + Duration::from_std(env.call_min_case_age)?,
+    };
+
+    if Utc::now() < next_call {
+        let content = if last_call.is_some() {
+            format!(
+                "The helpers have already been called to this support case. You can call them \
+                again at <t:{0}:F> (<t:{0}:R>).",
+                next_call.timestamp()
+            )
+        } else {
+            format!(
+                "You cannot call the helpers until <t:{0}:F> (<t:{0}:R>), {1} after opening your \
+                support case! We do this because all of our staff team is volunteers and we want \
+                to give them a chance to see and respond to your support case first before \
+                pinging them.",
+                next_call.timestamp(),
+                utils::chron::format_duration(Duration::from_std(env.call_min_case_age)?)?
+            )
+        };
+
+        poise::send_reply(ctx, |m| m.content(content)).await?;
+
+        return Ok(());
+    }
+
+    if !status.can_transition_to(Status::Called) {
+        poise::send_reply(ctx, |m| {
+            m.content(format!(
+                "The helpers cannot be called to a support case that is {}.",
+                status
+            ))
+        })
+        .await?;
+
+        return Ok(());
+    }
+
+    let (claimed_by, claimant_called_at): (Option<u64>, Option<String>) =
+        ctx.data().db.lock().unwrap().conn.query_row(
+            "SELECT claimed_by, claimant_called_at FROM support WHERE id = ?",
+            [&case_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+
+    // the helper who claimed the case gets a chance to respond before everyone is pinged
+    if let (Some(claimant), None) = (claimed_by, claimant_called_at) {
+        thread_id
+            .add_thread_member(&ctx.discord().http, UserId(claimant))
+            .await?;
+
+        {
+            let db = ctx.data().db.lock().unwrap();
+
+            db.conn.execute(
+                "UPDATE support SET claimant_called_at = ? WHERE id = ?",
+                [
+                    Utc::now()
+                        .to_rfc3339_opts(SecondsFormat::Millis, true)
+                        .as_str(),
+                    case_id.as_str(),
+                ],
+            )?;
+            db.set_status(
+                &case_id,
+                Status::Called,
+                Some(*ctx.author().id.as_u64()),
+                Some("claimant called"),
+            )?;
+            db.record_call(&case_id, *ctx.author().id.as_u64())?;
+        }
+
+        poise::send_reply(ctx, |m| {
+            m.content(format!(
+                "<@{}>, you have been called to the support case you claimed. If nobody \
+                responds, use the call command again to call all of the helpers.",
+                claimant
+            ))
+        })
+        .await?;
+
+        return Ok(());
+    }
+
+    let language: String = ctx.data().db.lock().unwrap().conn.query_row(
+        "SELECT language FROM support WHERE id = ?",
+        [&case_id],
+        |r| r.get(0),
+    )?;
+    let expertise_role = ctx
+        .data()
+        .config
+        .env
+        .language_role_ids
+        .get(&language.to_lowercase())
+        .map(|r| RoleId(*r));
+
+    let mut helpers: Vec<UserId> = match expertise_role {
+        Some(role) => ctx.data().roles.members_with(role),
+        None => vec![],
+    };
+
+    // nobody knows the language of the case, so every helper gets called instead
+    if helpers.is_empty() {
+        helpers = ctx
+            .data()
+            .roles
+            .members_with(RoleId(ctx.data().config.env.helper_role_id));
+    }
+
+    for helper in helpers {
+        thread_id
+            .add_thread_member(&ctx.discord().http, helper)
+            .await?;
+    }
+
+    {
+        let db = ctx.data().db.lock().unwrap();
+
+        db.set_status(
+            &case_id,
+            Status::Called,
+            Some(*ctx.author().id.as_u64()),
+            None,
+        )?;
+        db.record_call(&case_id, *ctx.author().id.as_u64())?;
+    }
+
+    poise::send_reply(ctx, |m| {
+        m.content("The helpers have been called to your support case.")
+    })
+    .await?;
+
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Claim Commands
+// ========================================================================================
+
+/// Assigns a support case to a helper and lets the thread know about it.
+async fn assign_case(
+    ctx: &SerenityContext,
+    state: &State,
+    case_id: &str,
+    thread_id: ChannelId,
+    helper_id: UserId,
+) -> Result<()> {
+    state.db.lock().unwrap().conn.execute(
+        "UPDATE support SET claimed_by = ?, claimed_at = ?, claimant_called_at = NULL WHERE id = ?",
+        [
+            helper_id.as_u64().to_string().as_str(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+                .as_str(),
+            case_id,
+        ],
+    )?;
+
+    thread_id.add_thread_member(&ctx.http, helper_id).await?;
+
+    Ok(())
+}
+
+fn claimed_by(state: &State, case_id: &str) -> Result<Option<u64>> {
+    Ok(state.db.lock().unwrap().conn.query_row(
+        "SELECT claimed_by FROM support WHERE id = ?",
+        [case_id],
+        |r| r.get(0),
+    )?)
+}
+
+/// Checks whether `author_id` may claim `case` for `helper_id`, returning why not if they
+/// cannot. Both the claim command and the claim button go through here.
+fn claim_refusal(
+    state: &State,
+    case: &Case,
+    author_id: UserId,
+    helper_id: UserId,
+) -> Option<String> {
+    if !is_helper_or_staff(state, author_id) {
+        return Some("Only helpers and staff members can claim a support case!".to_string());
+    }
+
+    if !case.status.is_active() {
+        return Some("Only open support cases can be claimed.".to_string());
+    }
+
+    // assigning someone else or taking over a claimed case is reserved for staff
+    let needs_staff =
+        helper_id != author_id || case.claimed_by.map_or(false, |c| &c != author_id.as_u64());
+
+    if needs_staff && !is_staff(state, author_id) {
+        return Some(match case.claimed_by {
+            Some(claimant) if helper_id == author_id => format!(
+                "This support case has already been claimed by <@{}>.",
+                claimant
+            ),
+            _ => "Only staff members can assign a support case to someone else!".to_string(),
+        });
+    }
+
+    if helper_id.as_u64() == &case.owner_id {
+        return Some("A support case cannot be claimed by its own author.".to_string());
+    }
+
+    if !is_helper_or_staff(state, helper_id) {
+        return Some(
+            "A support case can only be assigned to a helper or staff member.".to_string(),
+        );
+    }
+
+    None
+}
+
+async fn claim_from_button(
+    ctx: &SerenityContext,
+    state: &State,
+    mci: &MessageComponentInteraction,
+    case_id: &str,
+) -> Result<()> {
+    // every failure is answered, otherwise the user only sees that the interaction failed
+    let case = state.db.lock().unwrap().case_by_id(case_id);
+    let case = match case {
+        Ok(Some(case)) => case,
+        Ok(None) => {
+            reply_ephemeral(ctx, mci, "This support case could not be found.").await?;
+            return Ok(());
+        }
+        Err(error) => {
+            reply_ephemeral(
+                ctx,
+                mci,
+                "Something went wrong while claiming this support case.",
+            )
+            .await?;
+            return Err(error);
+        }
+    };
+
+    if let Some(refusal) = claim_refusal(state, &case, mci.user.id, mci.user.id) {
+        reply_ephemeral(ctx, mci, &refusal).await?;
+        return Ok(());
+    }
+
+    if let Err(error) = assign_case(ctx, state, case_id, mci.channel_id, mci.user.id).await {
+        reply_ephemeral(
+            ctx,
+            mci,
+            "Something went wrong while claiming this support case.",
+        )
+        .await?;
+        return Err(error);
+    }
+
+    mci.create_interaction_response(&ctx.http, |ir| {
+        ir.kind(InteractionResponseType::ChannelMessageWithSource);
+        ir.interaction_response_data(|d| {
+            d.content(format!(
+                "This support case has been claimed by <@{}>.",
+                mci.user.id
+            ))
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Claims a support case.
+///
+/// Claims the current support case so the other helpers know you are handling it. Staff members can also assign the support case to another helper. ```
+/// <<prefix>>claim [helper]
+/// ```
+#[poise::command(slash_command)]
+pub async fn claim(
+    ctx: Context<'_>,
+    #[description = "The helper to assign the support case to"] helper: Option<User>,
+) -> Result<()> {
+    let case = match current_case(ctx, "claim").await? {
+        Some(case) => case,
+        None => return Ok(()),
+    };
+
+    let helper_id = helper.map_or(ctx.author().id, |h| h.id);
+
+    if let Some(refusal) = claim_refusal(ctx.data(), &case, ctx.author().id, helper_id) {
+        poise::send_reply(ctx, |m| m.content(refusal)).await?;
+        return Ok(());
+    }
+
+    assign_case(
+        ctx.discord(),
+        ctx.data(),
+        &case.id,
+        ctx.channel_id(),
+        helper_id,
+    )
+    .await?;
+
+    poise::send_reply(ctx, |m| {
+        m.content(format!(
+            "This support case has been claimed by <@{}>.",
+            helper_id
+        ))
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Releases your claim on a support case.
+///
+/// Releases the claim on the current support case so another helper can take it over. Staff members can release anyone's claim. ```
+/// <<prefix>>unclaim
+/// ```
+#[poise::command(slash_command)]
+pub async fn unclaim(ctx: Context<'_>) -> Result<()> {
+    let case_id = match current_case(ctx, "unclaim").await? {
+        Some(case) => case.id,
+        None => return Ok(()),
+    };
 
-    let thread = support_channel
-        .create_public_thread(&ctx.discord().http, message.id, |t| {
-            t.name("case-".to_string() + &uuid);
-            t.auto_archive_duration(1440);
-            t.kind(ChannelType::PublicThread);
+    let claimant = match claimed_by(ctx.data(), &case_id)? {
+        Some(claimant) => claimant,
+        None => {
+            poise::send_reply(ctx, |m| {
+                m.content("This support case has not been claimed.")
+            })
+            .await?;
+            return Ok(());
+        }
+    };
 
-            t
+    if ctx.author().id.as_u64() != &claimant && !is_staff(ctx.data(), ctx.author().id) {
+        poise::send_reply(ctx, |m| {
+            m.content(
+                "Only the helper who claimed this support case and staff members can unclaim it!",
+            )
         })
         .await?;
+        return Ok(());
+    }
 
     ctx.data().db.lock().unwrap().conn.execute(
-        "INSERT INTO support (id, owner_id, thread_id, created_at) VALUES (:id, :owid, :thid, :creat)",
-            &[(":id", &uuid),
-            (":owid", &message.author.id.as_u64().to_string()),
-            (":thid", &thread.id.as_u64().to_string()),
-            (":creat", &Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))]
-        )?;
-
-    support_channel
-        .send_message(&ctx.discord().http, |m| {
-            m.content("Support case opened.");
+        "UPDATE support SET claimed_by = NULL, claimed_at = NULL, claimant_called_at = NULL WHERE id = ?",
+        [&case_id],
+    )?;
 
-            m
-        })
-        .await?;
+    poise::send_reply(ctx, |m| {
+        m.content(format!(
+            "<@{}> is no longer handling this support case, it can be claimed again.",
+            claimant
+        ))
+    })
+    .await?;
 
     Ok(())
 }
 
 // ========================================================================================
-//                                  Call Command
+//                                  Close Command
 // ========================================================================================
 
-/// Calls the helpers to your support case.
+/// Closes your support case.
 ///
-/// Calls the helpers to your support case. However, this command cannot be used unless it has been at least 30m since the case opened. ```
-/// <<prefix>>call
+/// Closes your support case so its not just sitting open even though it is done. ```
+/// <<prefix>>close
 /// ```
 #[poise::command(slash_command)]
-pub async fn call(ctx: Context<'_>) -> Result<()> {
+pub async fn close(ctx: Context<'_>) -> Result<()> {
     let thread_id = ctx.channel_id();
-    let thread: GuildChannel = thread_id
-        .to_channel(&ctx.discord().http)
-        .await?
-        .guild()
-        .unwrap();
-    let mut query_successful: bool = false;
-
-    let helpers = ctx
-        .guild_id()
-        .unwrap()
-        .members_iter(&ctx.discord().http)
-        .filter(|u| {
-            if let Some(u) = u.as_ref().ok() {
-                future::ready(
-                    u.roles
-                        .contains(&RoleId(ctx.data().config.env.helper_role_id)),
-                )
-            } else {
-                future::ready(false)
-            }
-        });
+    let (case_id, owner_id, status) = match current_case(ctx, "close").await? {
+        Some(case) => (case.id, case.owner_id, case.status),
+        None => return Ok(()),
+    };
 
-    if thread.kind != ChannelType::PublicThread || !thread.name.starts_with("case-") {
+    if ctx.author().id.as_u64() != &owner_id && !is_helper_or_staff(ctx.data(), ctx.author().id) {
         poise::send_reply(ctx, |m| {
-            m.content("The call command can only be used within support cases.")
+            m.content("Only the support case author and staff members can close a support case!")
         })
         .await?;
+        return Ok(());
+    }
+
+    // a case only counts as resolved if somebody actually answered it before it was closed
+    let new_status = if status == Status::Answered {
+        Status::Resolved
+    } else {
+        Status::Closed
+    };
 
+    if !status.can_transition_to(new_status) {
+        poise::send_reply(ctx, |m| {
+            m.content(format!("This support case is already {}.", status))
+        })
+        .await?;
         return Ok(());
     }
 
-    let created_at: String = match ctx.data().db.lock().unwrap().conn.query_row_and_then(
-        "SELECT created_at FROM support WHERE id = ?",
-        [thread.name[5..].to_string()],
-        |r| r.get(0),
-    ) {
-        Ok(timestamp) => {
-            query_successful = true;
-            timestamp
+    ctx.data().db.lock().unwrap().set_status(
+        &case_id,
+        new_status,
+        Some(*ctx.author().id.as_u64()),
+        None,
+    )?;
+
+    archive_case(
+        ctx,
+        &case_id,
+        UserId(owner_id),
+        thread_id,
+        "This support case has been closed and can only be re-opened by a staff member.",
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Wraps up a support case that was just closed or resolved, by letting the thread know,
+/// exporting its transcript, archiving and locking the thread and asking the owner for a rating.
+async fn archive_case(
+    ctx: Context<'_>,
+    case_id: &str,
+    owner_id: UserId,
+    thread_id: ChannelId,
+    content: &str,
+) -> Result<()> {
+    poise::send_reply(ctx, |m| m.content(content)).await?;
+
+    if let Err(error) = transcript::export(
+        &ctx.discord().http,
+        &ctx.data().config,
+        &ctx.data().hub,
+        &ctx.data().db,
+        case_id,
+        thread_id,
+    )
+    .await
+    {
+        println!(
+            "Unable to export the transcript of case-{}: {:?}",
+            case_id, error
+        );
+    }
+
+    thread_id
+        .edit_thread(ctx.discord(), |t| {
+            t.archived(true);
+            t.locked(true)
+        })
+        .await?;
+
+    // only the helpers who took part by the time the case closes get rated
+    let helpers: Vec<u64> = ctx
+        .data()
+        .db
+        .lock()
+        .unwrap()
+        .case_participants(case_id)?
+        .into_iter()
+        .filter(|p| is_helper_or_staff(ctx.data(), UserId(*p)))
+        .collect();
+
+    // waiting on the rating must not hold up the close, and the owner may have their DMs
+    // closed, which should not make the close fail either
+    let rating = prompt_rating(
+        ctx.discord().clone(),
+        ctx.data().config.clone(),
+        ctx.data().db.clone(),
+        case_id.to_string(),
+        owner_id,
+        helpers,
+    );
+    let case_id = case_id.to_string();
+    tokio::spawn(async move {
+        if let Err(error) = rating.await {
+            println!(
+                "Unable to ask for a rating of case-{}: {:?}",
+                case_id, error
+            );
         }
-        Err(_) => "Unable to get `created_at` for the current support case.".to_string(),
-    };
+    });
+
+    Ok(())
+}
 
-    if query_successful {
-        let duration: Duration = Utc::now() - created_at.parse::<DateTime<Utc>>()?;
+/// Asks the owner of a closed support case to rate the help they got, optionally with some
+/// written feedback, and stores the result against the case and its helpers. This waits on the
+/// owner for a long time, so it runs in its own task with owned copies of everything it needs.
+async fn prompt_rating(
+    ctx: SerenityContext,
+    config: Config,
+    db: Arc<Mutex<Database>>,
+    case_id: String,
+    owner_id: UserId,
+    helpers: Vec<u64>,
+) -> Result<()> {
+    let uuid_rating = Uuid::new_v4();
+    let timeout = config.env.rating_timeout;
+    let dm = owner_id.create_dm_channel(&ctx.http).await?;
 
-        if duration.num_minutes() < 30 {
-            poise::send_reply(ctx, |m| {
-                m.content(
-                    "You cannot call the helpers until at least 30m after opening your support case! \
-                  We do this because all of our staff team is volunteers and we want to give them \
-                  a chance to see and respond to your support case first before pinging them.",
-                )
-            })
-            .await?;
+    let mut prompt = dm
+        .send_message(&ctx.http, |m| {
+            m.embed(|embed| {
+                embed.title("How did we do?");
+                embed.description(format!(
+                    "Your support case case-{} has been closed. How happy are you with the help \
+                    you received?",
+                    case_id
+                ));
+                embed.color(config.env.default_embed_color);
+
+                embed
+            });
+            m.components(|c| {
+                c.create_action_row(|ar| {
+                    for rating in 1..=5 {
+                        ar.create_button(|b| {
+                            b.style(ButtonStyle::Secondary);
+                            b.label(format!("{} ⭐", rating));
+                            b.custom_id(format!("{}-{}", uuid_rating, rating));
+
+                            b
+                        });
+                    }
+                    ar
+                });
+                c
+            });
+
+            m
+        })
+        .await?;
+
+    let mov_uuid_rating = uuid_rating.to_string();
+    let mci = CollectComponentInteraction::new(&ctx)
+        .author_id(owner_id)
+        .message_id(prompt.id)
+        .timeout(timeout)
+        .filter(move |mci| mci.data.custom_id.starts_with(&mov_uuid_rating))
+        .await;
+
+    let mci = match mci {
+        Some(mci) => mci,
+        None => {
+            prompt
+                .edit(&ctx, |m| {
+                    m.components(|c| c);
+                    m.content(format!(
+                        "Interaction timed out at {} UTC",
+                        Utc::now().format("%Y-%m-%d %H:%M:%S")
+                    ))
+                })
+                .await?;
 
             return Ok(());
         }
-    }
+    };
 
-    for h in helpers.collect::<Vec<_>>().await.iter() {
-        let helper = h.as_ref().unwrap();
+    let rating: u8 = mci
+        .data
+        .custom_id
+        .rsplit('-')
+        .next()
+        .unwrap_or_default()
+        .parse()?;
 
-        thread_id
-            .add_thread_member(&ctx.discord().http, helper.user.id)
-            .await?;
-    }
+    db.lock()
+        .unwrap()
+        .record_rating(&case_id, *owner_id.as_u64(), rating, &helpers)?;
 
-    poise::send_reply(ctx, |m| {
-        m.content("The helpers have been called to your support case.")
+    let feedback_id = format!("{}-feedback", uuid_rating);
+
+    mci.create_interaction_response(&ctx, |ir| {
+        ir.kind(InteractionResponseType::UpdateMessage);
+        ir.interaction_response_data(|d| {
+            d.embeds(vec![]);
+            d.content(format!(
+                "Thank you! You rated support case case-{} with {}/5. If you want, you can \
+                also tell us what went well or what could be better.",
+                case_id, rating
+            ));
+            d.components(|c| {
+                c.create_action_row(|ar| {
+                    ar.create_button(|b| {
+                        b.style(ButtonStyle::Primary);
+                        b.label("Leave Feedback");
+                        b.custom_id(&feedback_id);
+
+                        b
+                    });
+                    ar
+                });
+                c
+            })
+        })
+    })
+    .await?;
+
+    let mov_feedback_id = feedback_id.clone();
+    let mci = CollectComponentInteraction::new(&ctx)
+        .author_id(owner_id)
+        .message_id(prompt.id)
+        .timeout(timeout)
+        .filter(move |mci| mci.data.custom_id == mov_feedback_id)
+        .await;
+
+    prompt.edit(&ctx, |m| m.components(|c| c)).await?;
+
+    let mci = match mci {
+        Some(mci) => mci,
+        None => return Ok(()),
+    };
+
+    let feedback_modal_id = format!("{}-modal", feedback_id);
+
+    mci.create_interaction_response(&ctx, |ir| {
+        ir.kind(InteractionResponseType::Modal);
+        ir.interaction_response_data(|d| {
+            d.custom_id(&feedback_modal_id);
+            d.title("Support Feedback");
+            d.components(|c| {
+                c.create_action_row(|ar| {
+                    ar.create_input_text(|it| {
+                        it.custom_id("feedback");
+                        it.label("Feedback");
+                        it.style(InputTextStyle::Paragraph);
+                        it.max_length(1000);
+                        it.required(true);
+
+                        it
+                    });
+                    ar
+                });
+                c
+            })
+        })
     })
     .await?;
 
+    let mov_feedback_modal_id = feedback_modal_id.clone();
+    let modal = CollectModalInteraction::new(&ctx)
+        .author_id(owner_id)
+        .timeout(timeout)
+        .filter(move |modal| modal.data.custom_id == mov_feedback_modal_id)
+        .await;
+
+    if let Some(modal) = modal {
+        let values = utils::interaction::modal_values(&modal)?;
+
+        if let Some(feedback) = values.get("feedback") {
+            db.lock().unwrap().record_feedback(&case_id, feedback)?;
+        }
+
+        modal
+            .create_interaction_response(&ctx, |ir| {
+                ir.kind(InteractionResponseType::ChannelMessageWithSource);
+                ir.interaction_response_data(|d| d.content("Thank you for your feedback!"))
+            })
+            .await?;
+    }
+
     Ok(())
 }
 
 // ========================================================================================
-//                                  Close Command
+//                                  Reopen Command
 // ========================================================================================
 
-/// Closes your support case.
+/// Re-opens a closed support case.
 ///
-/// Closes your support case so its not just sitting open even though it is done. ```
-/// <<prefix>>close
+/// Re-opens a closed support case, unarchiving and unlocking its thread. If no case is given, the support case of the current thread is re-opened. This command can only be used by staff members. ```
+/// <<prefix>>reopen <reason> [case]
 /// ```
 #[poise::command(slash_command)]
-pub async fn close(ctx: Context<'_>) -> Result<()> {
-    let thread_id = ctx.channel_id();
-    let thread: GuildChannel = thread_id
-        .to_channel(&ctx.discord().http)
-        .await?
-        .guild()
-        .unwrap();
-    let mut query_successful: bool = false;
-
-    if thread.kind != ChannelType::PublicThread || !thread.name.starts_with("case-") {
+pub async fn reopen(
+    ctx: Context<'_>,
+    #[description = "Why the support case is being re-opened"] reason: String,
+    #[description = "The ID of the support case to re-open"] case: Option<String>,
+) -> Result<()> {
+    if !is_helper_or_staff(ctx.data(), ctx.author().id) {
         poise::send_reply(ctx, |m| {
-            m.content("The close command can only be used within support cases.")
+            m.content("Only staff members can re-open a support case!")
         })
         .await?;
-
         return Ok(());
     }
 
-    let owner_id: u64 = match ctx.data().db.lock().unwrap().conn.query_row_and_then(
-        "SELECT owner_id FROM support WHERE id = ?",
-        [thread.name[5..].to_string()],
-        |r| r.get(0),
-    ) {
-        Ok(owner) => {
-            query_successful = true;
-            owner
+    let case_row: Option<(String, u64)> = {
+        let db = ctx.data().db.lock().unwrap();
+
+        match case {
+            Some(case) => {
+                let case_id = case::parse_id(&case);
+                db.case_thread(&case_id)?
+                    .map(|thread_id| (case_id, thread_id))
+            }
+            None => db
+                .case_by_thread(*ctx.channel_id().as_u64())?
+                .map(|case| (case.id, case.thread_id)),
         }
-        Err(_) => 0,
     };
 
-    if !query_successful {
-        poise::send_reply(ctx, |m| m.content("Unable to get the owner of this support case. As a result, the support case must manually be closed by staff.")).await?;
-        return Ok(());
-    }
+    let (case_id, thread_id) = match case_row {
+        Some(case_row) => case_row,
+        None => {
+            poise::send_reply(ctx, |m| {
+                m.content("Unable to find that support case. Either give a valid case ID or use the command within a support case.")
+            })
+            .await?;
+            return Ok(());
+        }
+    };
 
-    if ctx.author().id.as_u64() != &owner_id
-        && !ctx
-            .author()
-            .has_role(
-                &ctx.discord().http,
-                ctx.guild_id().unwrap(),
-                RoleId(ctx.data().config.env.helper_role_id),
-            )
-            .await?
-        && !ctx
-            .author()
-            .has_role(
-                &ctx.discord().http,
-                ctx.guild_id().unwrap(),
-                RoleId(ctx.data().config.env.staff_role_id),
-            )
-            .await?
-    {
+    let status = ctx.data().db.lock().unwrap().case_status(&case_id)?;
+
+    if !status.can_transition_to(Status::Reopened) {
         poise::send_reply(ctx, |m| {
-            m.content("Only the support case author and staff members can close a support case!")
+            m.content(format!(
+                "Only closed support cases can be re-opened, this one is {}.",
+                status
+            ))
         })
         .await?;
         return Ok(());
     }
 
-    poise::send_reply(ctx, |m| {
-        m.content("This support case has been closed and can only be re-opened by a staff member.")
-    })
-    .await?;
+    let thread: GuildChannel = ChannelId(thread_id)
+        .to_channel(&ctx.discord().http)
+        .await?
+        .guild()
+        .unwrap();
 
     thread
         .edit_thread(ctx.discord(), |t| {
-            t.archived(true);
-            t.locked(true)
+            t.archived(false);
+            t.locked(false)
+        })
+        .await?;
+
+    ctx.data().db.lock().unwrap().set_status(
+        &case_id,
+        Status::Reopened,
+        Some(*ctx.author().id.as_u64()),
+        Some(&reason),
+    )?;
+    ctx.data().db.lock().unwrap().record_activity(&case_id)?;
+
+    thread
+        .send_message(&ctx.discord().http, |m| {
+            m.embed(|embed| {
+                embed.title("Support Case Re-opened");
+                embed.description(format!(
+                    "This support case has been re-opened by <@{}>.",
+                    ctx.author().id
+                ));
+                embed.color(ctx.data().config.env.default_embed_color);
+                embed.field("Reason", &reason, false);
+
+                embed
+            })
+        })
+        .await?;
+
+    ChannelId(ctx.data().config.env.support_channel_id)
+        .send_message(&ctx.discord().http, |m| {
+            m.content(format!(
+                "Support case <#{}> has been re-opened by a staff member.",
+                thread_id
+            ))
+        })
+        .await?;
+
+    poise::send_reply(ctx, |m| m.content("Support case re-opened.")).await?;
+
+    Ok(())
+}
+
+// ========================================================================================
+//                                  Mark Solution Command
+// ========================================================================================
+
+/// Marks a message as the solution of a support case.
+///
+/// Marks a message in a support case as the answer that solved the problem. The message is pinned, its author is credited and the support case is resolved. This can only be used by the support case author and staff members. This is an context menu command only.
+#[poise::command(context_menu_command = "Mark as solution")]
+pub async fn mark_solution(
+    ctx: Context<'_>,
+    #[description = "Message that solved the support case"] msg: Message,
+) -> Result<()> {
+    let (case_id, owner_id, status) = match current_case(ctx, "mark as solution").await? {
+        Some(case) => (case.id, case.owner_id, case.status),
+        None => return Ok(()),
+    };
+
+    if ctx.author().id.as_u64() != &owner_id && !is_helper_or_staff(ctx.data(), ctx.author().id) {
+        poise::send_reply(ctx, |m| {
+            m.content(
+                "Only the support case author and staff members can mark the solution of a support case!",
+            )
+        })
+        .await?;
+        return Ok(());
+    }
+
+    if !status.can_transition_to(Status::Resolved) {
+        poise::send_reply(ctx, |m| {
+            m.content(format!("This support case is already {}.", status))
         })
         .await?;
+        return Ok(());
+    }
+
+    {
+        let db = ctx.data().db.lock().unwrap();
+
+        db.record_solution(
+            &case_id,
+            *msg.id.as_u64(),
+            *msg.author.id.as_u64(),
+            &msg.content,
+        )?;
+        db.set_status(
+            &case_id,
+            Status::Resolved,
+            Some(*ctx.author().id.as_u64()),
+            Some("solution marked"),
+        )?;
+    }
+
+    msg.pin(&ctx.discord().http).await?;
+
+    let credit = if msg.author.id.as_u64() == &owner_id {
+        "The support case author found the solution themselves.".to_string()
+    } else {
+        format!("Thanks to <@{}> for the solution!", msg.author.id)
+    };
+
+    archive_case(
+        ctx,
+        &case_id,
+        UserId(owner_id),
+        ctx.channel_id(),
+        &format!(
+            "This support case has been resolved by [this message]({}). {}",
+            msg.link(),
+            credit
+        ),
+    )
+    .await?;
 
     Ok(())
 }
@@ -224,32 +1914,15 @@ pub async fn close(ctx: Context<'_>) -> Result<()> {
 
 /// Creates a support case from a message.
 ///
-/// Allows staff members to create a support case from an existing message. This is an context menu command only.
+/// Allows staff members to create a support case from an existing message. The author of the message becomes the owner of the support case and its attachments and embeds are moved along with it, afterwards the original message can be deleted. This is an context menu command only.
 #[poise::command(context_menu_command = "Move to support case")]
 pub async fn case_from_message(
     ctx: Context<'_>,
     #[description = "Message to move to support case"] msg: Message,
 ) -> Result<()> {
-    let uuid: String = Uuid::new_v4().to_string()[..6].to_string();
     let support_channel = ChannelId(ctx.data().config.env.support_channel_id);
 
-    if !ctx
-        .author()
-        .has_role(
-            &ctx.discord().http,
-            ctx.guild_id().unwrap(),
-            RoleId(ctx.data().config.env.helper_role_id),
-        )
-        .await?
-        && !ctx
-            .author()
-            .has_role(
-                &ctx.discord().http,
-                ctx.guild_id().unwrap(),
-                RoleId(ctx.data().config.env.staff_role_id),
-            )
-            .await?
-    {
+    if !is_helper_or_staff(ctx.data(), ctx.author().id) {
         poise::send_reply(ctx, |m| {
             m.content("Only staff members may create a support case from a message!")
         })
@@ -260,24 +1933,29 @@ pub async fn case_from_message(
     let thread_msg = support_channel
         .send_message(&ctx.discord().http, |m| {
             m.content(format!(
-                "{}, your message has moved to new support case by a staff member.",
-                ctx.author().name
+                "<@{}>, your message has been moved to a new support case by <@{}>.",
+                msg.author.id,
+                ctx.author().id
             ))
         })
         .await?;
 
-    let thread = support_channel
-        .create_public_thread(&ctx.discord().http, thread_msg.id, |t| {
-            t.name("case-".to_string() + &uuid);
-            t.auto_archive_duration(1440);
-            t.kind(ChannelType::PublicThread);
+    let (_, thread) = start_case(
+        ctx.discord(),
+        ctx.data(),
+        msg.author.id,
+        thread_msg.id,
+        None,
+        &msg.content,
+        detect_language(&msg)?,
+        Some("moved from message"),
+    )
+    .await?;
 
-            t
-        })
+    thread
+        .add_thread_member(&ctx.discord().http, msg.author.id)
         .await?;
-
     thread
-        .id
         .add_thread_member(&ctx.discord().http, ctx.author().id)
         .await?;
 
@@ -285,8 +1963,13 @@ pub async fn case_from_message(
         .send_message(&ctx.discord().http, |m| {
             m.embed(|embed| {
                 embed.title("Original Message");
+                embed.author(|a| {
+                    a.name(&msg.author.name);
+                    a.icon_url(msg.author.face())
+                });
                 embed.description(&msg.content);
                 embed.color(ctx.data().config.env.default_embed_color);
+                embed.timestamp(&msg.timestamp);
                 embed.field(
                     "Context",
                     format!("[Jump to Original]({})", msg.link()),
@@ -298,15 +1981,73 @@ pub async fn case_from_message(
         })
         .await?;
 
-    ctx.data().db.lock().unwrap().conn.execute(
-        "INSERT INTO support (id, owner_id, thread_id, created_at) VALUES (:id, :owid, :thid, :creat)",
-            &[(":id", &uuid),
-            (":owid", &ctx.author().id.as_u64().to_string()),
-            (":thid", &thread.id.as_u64().to_string()),
-            (":creat", &Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))]
-        )?;
+    // attachments are downloaded so they survive the original message being deleted
+    let mut files: Vec<AttachmentType> = Vec::new();
+
+    for attachment in &msg.attachments {
+        files.push(AttachmentType::Bytes {
+            data: Cow::from(attachment.download().await?),
+            filename: attachment.filename.to_owned(),
+        });
+    }
+    let embeds: Vec<CreateEmbed> = msg.embeds.iter().cloned().map(CreateEmbed::from).collect();
+
+    if !files.is_empty() {
+        thread
+            .send_files(&ctx.discord().http, files, |m| m.set_embeds(embeds))
+            .await?;
+    } else if !embeds.is_empty() {
+        thread
+            .send_message(&ctx.discord().http, |m| m.set_embeds(embeds))
+            .await?;
+    }
+
+    let uuid_delete = Uuid::new_v4().to_string();
+
+    poise::send_reply(ctx, |m| {
+        m.content(format!(
+            "Support case created from message in <#{}>. Do you want to delete the original message?",
+            thread
+        ));
+        m.components(|c| {
+            c.create_action_row(|ar| {
+                ar.create_button(|b| {
+                    b.style(ButtonStyle::Danger);
+                    b.label("Delete Original");
+                    b.custom_id(&uuid_delete);
+
+                    b
+                });
+                ar
+            });
+            c
+        });
+        m.ephemeral(true)
+    })
+    .await?;
+
+    let mov_uuid_delete = uuid_delete.clone();
+    let mci = CollectComponentInteraction::new(ctx.discord())
+        .author_id(ctx.author().id)
+        .timeout(ctx.data().config.env.default_interaction_timeout)
+        .filter(move |mci| mci.data.custom_id == mov_uuid_delete)
+        .await;
+
+    if let Some(mci) = mci {
+        msg.delete(&ctx.discord().http).await?;
 
-    poise::send_reply(ctx, |m| m.content("Support case created from message.")).await?;
+        mci.create_interaction_response(ctx.discord(), |ir| {
+            ir.kind(InteractionResponseType::UpdateMessage);
+            ir.interaction_response_data(|d| {
+                d.content(format!(
+                    "Support case created from message in <#{}>. The original message has been deleted.",
+                    thread
+                ));
+                d.components(|c| c)
+            })
+        })
+        .await?;
+    }
 
     Ok(())
 }
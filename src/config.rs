@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use dotenv::dotenv;
+use std::collections::HashMap;
 use std::env::var;
 use std::time::Duration;
 
@@ -19,9 +20,20 @@ pub struct Env {
     pub default_interaction_timeout: Duration,
     pub hub_server_id: u64,
     pub hub_stdout_id: u64,
+    pub hub_archive_id: u64,
+    pub hub_escalation_id: u64,
     pub support_channel_id: u64,
     pub helper_role_id: u64,
+    pub language_role_ids: HashMap<String, u64>,
     pub staff_role_id: u64,
+    pub case_idle_warning: Duration,
+    pub case_idle_close: Duration,
+    pub call_min_case_age: Duration,
+    pub call_cooldown: Duration,
+    pub call_limit: u32,
+    pub case_escalation_delay: Duration,
+    pub rating_timeout: Duration,
+    pub open_case_limit: u32,
 }
 
 #[derive(Clone)]
@@ -71,9 +83,37 @@ impl Env {
             ),
             hub_server_id: var("HUB_SERVER_ID")?.parse()?,
             hub_stdout_id: var("HUB_STDOUT_ID")?.parse()?,
+            hub_archive_id: var("HUB_ARCHIVE_ID")?.parse()?,
+            hub_escalation_id: var("HUB_ESCALATION_ID")?.parse()?,
             support_channel_id: var("SUPPORT_CHANNEL_ID")?.parse()?,
             helper_role_id: var("HELPER_ROLE_ID")?.parse()?,
+            // optional, without it every call goes to the general helper role
+            language_role_ids: parse_language_roles(&var("LANGUAGE_ROLE_IDS").unwrap_or_default())?,
             staff_role_id: var("STAFF_ROLE_ID")?.parse()?,
+            case_idle_warning: Duration::from_secs(var("CASE_IDLE_WARNING")?.parse()?),
+            case_idle_close: Duration::from_secs(var("CASE_IDLE_CLOSE")?.parse()?),
+            call_min_case_age: Duration::from_secs(var("CALL_MIN_CASE_AGE")?.parse()?),
+            call_cooldown: Duration::from_secs(var("CALL_COOLDOWN")?.parse()?),
+            call_limit: var("CALL_LIMIT")?.parse()?,
+            case_escalation_delay: Duration::from_secs(var("CASE_ESCALATION_DELAY")?.parse()?),
+            rating_timeout: Duration::from_secs(var("RATING_TIMEOUT")?.parse()?),
+            open_case_limit: var("OPEN_CASE_LIMIT")?.parse()?,
         })
     }
 }
+
+/// Parses a list of `language:role_id` pairs, e.g. `rust:123,python:456`. Languages are
+/// stored in lowercase so they can be matched against the language of a support case.
+fn parse_language_roles(raw: &str) -> Result<HashMap<String, u64>> {
+    let mut roles: HashMap<String, u64> = HashMap::new();
+
+    for pair in raw.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let (language, role_id) = pair
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("Invalid language role `{}`", pair))?;
+
+        roles.insert(language.trim().to_lowercase(), role_id.trim().parse()?);
+    }
+
+    Ok(roles)
+}
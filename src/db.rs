@@ -1,8 +1,54 @@
-use anyhow::Result;
-use rusqlite::Connection;
+use crate::case::{Case, Status};
+use anyhow::{anyhow, Result};
+use chrono::{prelude::Utc, SecondsFormat};
+use rusqlite::{Connection, OptionalExtension};
+
+/// Statuses of cases that are still waiting on someone, for use in `IN` clauses.
+const ACTIVE_STATUSES: &str = "('open', 'called', 'answered', 'reopened')";
+
+/// The number given to the first support case opened with sequential case numbers.
+const FIRST_CASE_NUMBER: i64 = 1001;
+
+/// Columns needed to build a [`Case`], in the order [`case_row`] reads them.
+const CASE_COLUMNS: &str = "id, owner_id, thread_id, created_at, language, title, status, \
+    claimed_by, claimed_at, escalated_at";
+
+/// A raw `support` row as read by [`case_row`].
+pub type CaseRow = (
+    String,
+    u64,
+    String,
+    String,
+    String,
+    String,
+    String,
+    Option<u64>,
+    Option<String>,
+    Option<String>,
+);
+
+/// A knowledge base match as `(id, thread_id, title, language, snippet)`.
+pub type KbMatch = (String, u64, String, String, String);
+
+fn case_row(r: &rusqlite::Row) -> rusqlite::Result<CaseRow> {
+    Ok((
+        r.get(0)?,
+        r.get(1)?,
+        r.get(2)?,
+        r.get(3)?,
+        r.get(4)?,
+        r.get(5)?,
+        r.get(6)?,
+        r.get(7)?,
+        r.get(8)?,
+        r.get(9)?,
+    ))
+}
 
 pub struct Database {
     pub conn: Connection,
+    /// Whether SQLite was built with FTS5, without it the knowledge base stays empty.
+    pub kb_enabled: bool,
 }
 
 impl Database {
@@ -21,7 +67,836 @@ impl Database {
             )",
             [],
         )?;
+        // every message sent in a thread looks its case up by thread
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS support_thread_id ON support (thread_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_status (
+                case_id text NOT NULL,
+                status text NOT NULL,
+                changed_by integer,
+                changed_at text NOT NULL,
+                note text
+            )",
+            [],
+        )?;
+
+        // cases opened before the lifecycle existed were never closed in the database, they have
+        // no status history and would otherwise look open forever
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+
+        if version < 1 {
+            let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+            conn.execute(
+                "INSERT INTO support_status (case_id, status, changed_at, note)
+                    SELECT id, 'closed', ?, 'closed before status tracking' FROM support
+                    WHERE id NOT IN (SELECT case_id FROM support_status)",
+                [now.as_str()],
+            )?;
+            conn.execute(
+                "UPDATE support SET status = 'closed'
+                    WHERE id IN (
+                        SELECT case_id FROM support_status
+                        WHERE note = 'closed before status tracking'
+                    )",
+                [],
+            )?;
+            conn.execute("PRAGMA user_version = 1", [])?;
+        }
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_edits (
+                case_id text NOT NULL,
+                message_id text NOT NULL,
+                content text NOT NULL,
+                edited_at text NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS support_edits_message_id ON support_edits (message_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_calls (
+                case_id text NOT NULL,
+                called_by integer NOT NULL,
+                called_at text NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_participants (
+                case_id text NOT NULL,
+                user_id integer NOT NULL,
+                first_message_at text NOT NULL,
+                message_count integer NOT NULL DEFAULT 1,
+                PRIMARY KEY (case_id, user_id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_ratings (
+                case_id text NOT NULL PRIMARY KEY,
+                owner_id integer NOT NULL,
+                rating integer NOT NULL,
+                feedback text,
+                rated_at text NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_rating_helpers (
+                case_id text NOT NULL,
+                helper_id integer NOT NULL,
+                PRIMARY KEY (case_id, helper_id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_notes (
+                id integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+                case_id text NOT NULL,
+                author_id integer NOT NULL,
+                content text NOT NULL,
+                created_at text NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS support_sequence (
+                name text NOT NULL PRIMARY KEY,
+                value integer NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO support_sequence (name, value) VALUES ('case', ?)",
+            [FIRST_CASE_NUMBER - 1],
+        )?;
+
+        ensure_column(&conn, "support", "last_activity_at", "text")?;
+        ensure_column(&conn, "support", "idle_warned_at", "text")?;
+        ensure_column(&conn, "support", "tried", "text")?;
+        ensure_column(&conn, "support", "summary_message_id", "text")?;
+        ensure_column(&conn, "support", "transcript_path", "text")?;
+        ensure_column(&conn, "support", "claimed_by", "integer")?;
+        ensure_column(&conn, "support", "claimed_at", "text")?;
+        ensure_column(&conn, "support", "claimant_called_at", "text")?;
+        ensure_column(&conn, "support", "first_response_at", "text")?;
+        ensure_column(&conn, "support", "last_response_at", "text")?;
+        ensure_column(&conn, "support", "escalated_at", "text")?;
+        ensure_column(&conn, "support", "resolved_at", "text")?;
+        ensure_column(&conn, "support", "solution_message_id", "text")?;
+        ensure_column(&conn, "support", "solution_author_id", "integer")?;
+        ensure_column(&conn, "support", "solution", "text")?;
+        ensure_column(&conn, "support", "question", "text")?;
+
+        // the system SQLite is used, which is not always built with FTS5
+        let kb_enabled = match conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS support_kb USING fts5(
+                case_id UNINDEXED,
+                title,
+                language,
+                question,
+                solution
+            )",
+            [],
+        ) {
+            Ok(_) => true,
+            Err(error) => {
+                println!("Knowledge base disabled, FTS5 is unavailable: {}", error);
+                false
+            }
+        };
+        if kb_enabled {
+            // resolved cases from before the knowledge base existed
+            conn.execute(
+                "INSERT INTO support_kb (case_id, title, language, question, solution)
+                    SELECT id, title, language, COALESCE(question, ''), solution
+                    FROM support
+                    WHERE status = 'resolved' AND solution IS NOT NULL
+                        AND id NOT IN (SELECT case_id FROM support_kb)",
+                [],
+            )?;
+        }
+
+        Ok(Self { conn, kb_enabled })
+    }
+
+    /// Allocates the next sequential case number. A number is never handed out twice, even if
+    /// opening the case fails afterwards, and numbers already used by an old hex ID are skipped.
+    pub fn next_case_id(&mut self) -> Result<String> {
+        let tx = self.conn.transaction()?;
+        let mut number: i64 = tx.query_row(
+            "SELECT value FROM support_sequence WHERE name = 'case'",
+            [],
+            |r| r.get(0),
+        )?;
+
+        loop {
+            number += 1;
+
+            let taken: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM support WHERE id = ?)",
+                [number.to_string()],
+                |r| r.get(0),
+            )?;
+            if !taken {
+                break;
+            }
+        }
+
+        tx.execute(
+            "UPDATE support_sequence SET value = ? WHERE name = 'case'",
+            [number],
+        )?;
+        tx.commit()?;
+
+        Ok(number.to_string())
+    }
+
+    /// Loads the full support case living in the given thread.
+    pub fn case_by_thread(&self, thread_id: u64) -> Result<Option<Case>> {
+        let row: Option<CaseRow> = self
+            .conn
+            .query_row(
+                &format!("SELECT {} FROM support WHERE thread_id = ?", CASE_COLUMNS),
+                [thread_id.to_string()],
+                case_row,
+            )
+            .optional()?;
+
+        row.map(Case::from_row).transpose()
+    }
+
+    /// Loads the full support case with the given ID.
+    pub fn case_by_id(&self, id: &str) -> Result<Option<Case>> {
+        let row: Option<CaseRow> = self
+            .conn
+            .query_row(
+                &format!("SELECT {} FROM support WHERE id = ?", CASE_COLUMNS),
+                [id],
+                case_row,
+            )
+            .optional()?;
+
+        row.map(Case::from_row).transpose()
+    }
+
+    /// Finds the thread a support case lives in.
+    pub fn case_thread(&self, id: &str) -> Result<Option<u64>> {
+        let thread_id: Option<String> = self
+            .conn
+            .query_row("SELECT thread_id FROM support WHERE id = ?", [id], |r| {
+                r.get(0)
+            })
+            .optional()?;
+
+        match thread_id {
+            Some(thread_id) => Ok(Some(thread_id.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn case_status(&self, id: &str) -> Result<Status> {
+        let status: String =
+            self.conn
+                .query_row("SELECT status FROM support WHERE id = ?", [id], |r| {
+                    r.get(0)
+                })?;
+
+        status.parse()
+    }
+
+    /// Appends an entry to the status history of a case without touching the case row.
+    pub fn log_status(
+        &self,
+        id: &str,
+        status: Status,
+        changed_by: Option<u64>,
+        note: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO support_status (case_id, status, changed_by, changed_at, note)
+                VALUES (:id, :status, :chby, :chat, :note)",
+            rusqlite::named_params! {
+                ":id": id,
+                ":status": status.as_str(),
+                ":chby": changed_by.map(|u| u.to_string()),
+                ":chat": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                ":note": note,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Moves a case to a new status, returning the status it had before. Fails if the
+    /// lifecycle does not allow going from the current status to the new one.
+    pub fn set_status(
+        &self,
+        id: &str,
+        status: Status,
+        changed_by: Option<u64>,
+        note: Option<&str>,
+    ) -> Result<Status> {
+        let previous = self.case_status(id)?;
+
+        if !previous.can_transition_to(status) {
+            return Err(anyhow!(
+                "Support case {} cannot go from {} to {}",
+                id,
+                previous,
+                status
+            ));
+        }
+
+        self.conn.execute(
+            "UPDATE support SET status = ? WHERE id = ?",
+            [status.as_str(), id],
+        )?;
+        if status == Status::Resolved {
+            self.conn.execute(
+                "UPDATE support SET resolved_at = ? WHERE id = ?",
+                [
+                    Utc::now()
+                        .to_rfc3339_opts(SecondsFormat::Millis, true)
+                        .as_str(),
+                    id,
+                ],
+            )?;
+        }
+        self.index_case(id, status)?;
+        self.log_status(id, status, changed_by, note)?;
+
+        Ok(previous)
+    }
+
+    /// Keeps the knowledge base in sync with a case, only resolved cases with a solution are
+    /// part of it.
+    fn index_case(&self, id: &str, status: Status) -> Result<()> {
+        if !self.kb_enabled {
+            return Ok(());
+        }
+
+        self.conn
+            .execute("DELETE FROM support_kb WHERE case_id = ?", [id])?;
+
+        if status == Status::Resolved {
+            self.conn.execute(
+                "INSERT INTO support_kb (case_id, title, language, question, solution)
+                    SELECT id, title, language, COALESCE(question, ''), solution
+                    FROM support WHERE id = ? AND solution IS NOT NULL",
+                [id],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Searches the knowledge base of resolved cases, best matches first. Any word of the query
+    /// can match.
+    pub fn search_kb(&self, query: &str, limit: u32) -> Result<Vec<KbMatch>> {
+        let query = match kb_query(query) {
+            Some(query) if self.kb_enabled => query,
+            _ => return Ok(vec![]),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT support_kb.case_id, s.thread_id, s.title, s.language,
+                    snippet(support_kb, -1, '**', '**', '…', 16)
+                FROM support_kb
+                JOIN support s ON s.id = support_kb.case_id
+                WHERE support_kb MATCH ?
+                ORDER BY bm25(support_kb, 0.0, 10.0, 2.0, 5.0, 3.0)
+                LIMIT ?",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![query, limit], |r| {
+                Ok((
+                    r.get(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get(2)?,
+                    r.get(3)?,
+                    r.get(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, String, String, String, String)>>>()?;
+
+        rows.into_iter()
+            .map(|(id, thread_id, title, language, snippet)| {
+                Ok((id, thread_id.parse()?, title, language, snippet))
+            })
+            .collect()
+    }
+
+    /// Resolved cases sharing words with `text` as `(id, thread_id, title, question)`, best
+    /// matches first, for comparing them to a new question.
+    pub fn kb_candidates(
+        &self,
+        text: &str,
+        limit: u32,
+    ) -> Result<Vec<(String, u64, String, String)>> {
+        let query = match kb_query(text) {
+            Some(query) if self.kb_enabled => query,
+            _ => return Ok(vec![]),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT support_kb.case_id, s.thread_id, support_kb.title, support_kb.question
+                FROM support_kb
+                JOIN support s ON s.id = support_kb.case_id
+                WHERE support_kb MATCH ?
+                ORDER BY bm25(support_kb, 0.0, 10.0, 2.0, 5.0, 3.0)
+                LIMIT ?",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![query, limit], |r| {
+                Ok((r.get(0)?, r.get::<_, String>(1)?, r.get(2)?, r.get(3)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, String, String, String)>>>()?;
+
+        rows.into_iter()
+            .map(|(id, thread_id, title, question)| Ok((id, thread_id.parse()?, title, question)))
+            .collect()
+    }
+
+    pub fn record_call(&self, id: &str, called_by: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO support_calls (case_id, called_by, called_at) VALUES (?, ?, ?)",
+            [
+                id,
+                called_by.to_string().as_str(),
+                Utc::now()
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+                    .as_str(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Every time the helpers were called to a case as `(called_by, called_at)`, oldest first.
+    pub fn case_calls(&self, id: &str) -> Result<Vec<(u64, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT called_by, called_at FROM support_calls WHERE case_id = ? ORDER BY called_at",
+        )?;
+        let calls = stmt
+            .query_map([id], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(u64, String)>>>()?;
+
+        Ok(calls)
+    }
+
+    /// Counts a message by someone other than the owner towards their participation in a case.
+    pub fn record_participant(&self, id: &str, user_id: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO support_participants (case_id, user_id, first_message_at)
+                VALUES (?, ?, ?)
+                ON CONFLICT (case_id, user_id) DO UPDATE SET message_count = message_count + 1",
+            [
+                id,
+                user_id.to_string().as_str(),
+                Utc::now()
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+                    .as_str(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Everyone other than the owner who posted in a case, in the order they joined.
+    pub fn case_participants(&self, id: &str) -> Result<Vec<u64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT user_id FROM support_participants WHERE case_id = ? ORDER BY first_message_at",
+        )?;
+        let participants = stmt
+            .query_map([id], |r| r.get(0))?
+            .collect::<rusqlite::Result<Vec<u64>>>()?;
+
+        Ok(participants)
+    }
+
+    /// Stores the rating the owner gave a case, crediting it to the helpers who took part.
+    pub fn record_rating(
+        &self,
+        id: &str,
+        owner_id: u64,
+        rating: u8,
+        helpers: &[u64],
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO support_ratings (case_id, owner_id, rating, rated_at)
+                VALUES (?, ?, ?, ?)",
+            [
+                id,
+                owner_id.to_string().as_str(),
+                rating.to_string().as_str(),
+                Utc::now()
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+                    .as_str(),
+            ],
+        )?;
+        self.conn
+            .execute("DELETE FROM support_rating_helpers WHERE case_id = ?", [id])?;
+
+        for helper in helpers {
+            self.conn.execute(
+                "INSERT INTO support_rating_helpers (case_id, helper_id) VALUES (?, ?)",
+                [id, helper.to_string().as_str()],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn record_feedback(&self, id: &str, feedback: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE support_ratings SET feedback = ? WHERE case_id = ?",
+            [feedback, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Stores which message solved a case, who wrote it and what it said.
+    pub fn record_solution(
+        &self,
+        id: &str,
+        message_id: u64,
+        author_id: u64,
+        content: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE support SET solution_message_id = :msid, solution_author_id = :auid,
+                solution = :content WHERE id = :id",
+            rusqlite::named_params! {
+                ":msid": message_id.to_string(),
+                ":auid": author_id.to_string(),
+                ":content": content,
+                ":id": id,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Adds a staff note to a case and returns the ID of the note.
+    pub fn add_note(&self, id: &str, author_id: u64, content: &str) -> Result<i64> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        self.conn.execute(
+            "INSERT INTO support_notes (case_id, author_id, content, created_at) VALUES (?, ?, ?, ?)",
+            [id, author_id.to_string().as_str(), content, now.as_str()],
+        )?;
+
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Staff notes of a case as `(note_id, author_id, content, created_at)`, oldest first.
+    pub fn case_notes(&self, id: &str) -> Result<Vec<(i64, u64, String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, author_id, content, created_at FROM support_notes
+                WHERE case_id = ? ORDER BY id",
+        )?;
+        let notes = stmt
+            .query_map([id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))?
+            .collect::<rusqlite::Result<Vec<(i64, u64, String, String)>>>()?;
+
+        Ok(notes)
+    }
+
+    /// Finds the author of a note on a case, `None` if the case has no such note.
+    pub fn note_author(&self, id: &str, note_id: i64) -> Result<Option<u64>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT author_id FROM support_notes WHERE case_id = ? AND id = ?",
+                [id, note_id.to_string().as_str()],
+                |r| r.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn delete_note(&self, id: &str, note_id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM support_notes WHERE case_id = ? AND id = ?",
+            [id, note_id.to_string().as_str()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Records that a helper posted in a case right now.
+    pub fn record_response(&self, id: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        self.conn.execute(
+            "UPDATE support SET first_response_at = COALESCE(first_response_at, ?1),
+                last_response_at = ?1 WHERE id = ?2",
+            [now.as_str(), id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Marks a case as active right now, clearing any pending inactivity warning.
+    pub fn record_activity(&self, id: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        self.conn.execute(
+            "UPDATE support SET last_activity_at = ?, idle_warned_at = NULL WHERE id = ?",
+            [now.as_str(), id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Active cases without any activity since `before` that have not been warned yet, as
+    /// `(id, owner_id, thread_id)`.
+    pub fn idle_cases(&self, before: &str) -> Result<Vec<(String, u64, u64)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, owner_id, thread_id FROM support
+                WHERE status IN {}
+                AND idle_warned_at IS NULL
+                AND COALESCE(last_activity_at, created_at) < ?",
+            ACTIVE_STATUSES
+        ))?;
+        let rows = stmt
+            .query_map([before], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get::<_, String>(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, u64, String)>>>()?;
+
+        rows.into_iter()
+            .map(|(id, owner_id, thread_id)| Ok((id, owner_id, thread_id.parse()?)))
+            .collect()
+    }
+
+    /// Active cases whose last call was before `before` without a helper responding since, and
+    /// that were not escalated after that call yet, as `(id, thread_id)`.
+    pub fn unanswered_calls(&self, before: &str) -> Result<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, thread_id FROM (
+                SELECT id, thread_id, last_response_at, escalated_at,
+                    (SELECT MAX(called_at) FROM support_calls WHERE case_id = support.id) AS last_call
+                FROM support WHERE status IN {}
+            )
+            WHERE last_call IS NOT NULL
+                AND last_call < ?
+                AND (last_response_at IS NULL OR last_response_at < last_call)
+                AND (escalated_at IS NULL OR escalated_at < last_call)",
+            ACTIVE_STATUSES
+        ))?;
+        let rows = stmt
+            .query_map([before], |r| Ok((r.get(0)?, r.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+
+        rows.into_iter()
+            .map(|(id, thread_id)| Ok((id, thread_id.parse()?)))
+            .collect()
+    }
+
+    pub fn mark_escalated(&self, id: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        self.conn.execute(
+            "UPDATE support SET escalated_at = ? WHERE id = ?",
+            [now.as_str(), id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Active cases opened by a user as `(id, thread_id)`, oldest first.
+    pub fn open_cases(&self, owner_id: u64) -> Result<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, thread_id FROM support
+                WHERE owner_id = ? AND status IN {}
+                ORDER BY created_at",
+            ACTIVE_STATUSES
+        ))?;
+        let rows = stmt
+            .query_map([owner_id.to_string()], |r| {
+                Ok((r.get(0)?, r.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+
+        rows.into_iter()
+            .map(|(id, thread_id)| Ok((id, thread_id.parse()?)))
+            .collect()
+    }
+
+    /// Active cases that were warned about inactivity before `before` and are still idle, as
+    /// `(id, thread_id)`.
+    pub fn expired_cases(&self, before: &str) -> Result<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, thread_id FROM support
+                WHERE status IN {}
+                AND idle_warned_at IS NOT NULL
+                AND idle_warned_at < ?",
+            ACTIVE_STATUSES
+        ))?;
+        let rows = stmt
+            .query_map([before], |r| Ok((r.get(0)?, r.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+
+        rows.into_iter()
+            .map(|(id, thread_id)| Ok((id, thread_id.parse()?)))
+            .collect()
+    }
+
+    /// Stores a version of a message in a support case, so edits show up in its transcript. A
+    /// version that is the same as the latest stored one is skipped.
+    pub fn record_edit(
+        &self,
+        id: &str,
+        message_id: u64,
+        content: &str,
+        edited_at: &str,
+    ) -> Result<()> {
+        let latest: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT content FROM support_edits WHERE message_id = ?
+                    ORDER BY edited_at DESC LIMIT 1",
+                [message_id.to_string()],
+                |r| r.get(0),
+            )
+            .optional()?;
+
+        if latest.as_deref() == Some(content) {
+            return Ok(());
+        }
+
+        self.conn.execute(
+            "INSERT INTO support_edits (case_id, message_id, content, edited_at)
+                VALUES (?, ?, ?, ?)",
+            [id, message_id.to_string().as_str(), content, edited_at],
+        )?;
+
+        Ok(())
+    }
+
+    /// All recorded versions of the messages in a case, as `(message_id, content, edited_at)`
+    /// ordered from oldest to newest.
+    pub fn case_edits(&self, id: &str) -> Result<Vec<(u64, String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id, content, edited_at FROM support_edits
+                WHERE case_id = ? ORDER BY edited_at",
+        )?;
+        let rows = stmt
+            .query_map([id], |r| Ok((r.get::<_, String>(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<(String, String, String)>>>()?;
+
+        rows.into_iter()
+            .map(|(message_id, content, edited_at)| Ok((message_id.parse()?, content, edited_at)))
+            .collect()
+    }
+
+    pub fn mark_idle_warned(&self, id: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        self.conn.execute(
+            "UPDATE support SET idle_warned_at = ? WHERE id = ?",
+            [now.as_str(), id],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Turns free text into an FTS5 query matching any of its words. Every word is quoted, which
+/// keeps FTS5 from reading the text as its own query syntax. `None` if there are no words.
+fn kb_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{}\"", w))
+        .collect();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    Some(words.join(" OR "))
+}
+
+/// Adds a column to an existing table if it is not there yet, so databases created by older
+/// versions of the bot pick up new columns.
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |r| r.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<String>>>()?
+        .iter()
+        .any(|c| c == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Database;
+    use std::fs;
+
+    /// Loads a fresh database in its own directory under the system temp directory.
+    fn temp_database(name: &str) -> Database {
+        let path = std::env::temp_dir().join(format!("dogeflow-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        Database::load(&path.to_string_lossy().to_string()).unwrap()
+    }
+
+    fn insert_case(db: &Database, id: &str) {
+        db.conn
+            .execute(
+                "INSERT INTO support (id, owner_id, thread_id, created_at)
+                    VALUES (?, 1, ?, '2022-01-01T00:00:00.000Z')",
+                [id, id],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn case_ids_are_sequential() {
+        let mut db = temp_database("sequential");
+
+        assert_eq!(db.next_case_id().unwrap(), "1001");
+        assert_eq!(db.next_case_id().unwrap(), "1002");
+        assert_eq!(db.next_case_id().unwrap(), "1003");
+    }
+
+    #[test]
+    fn case_ids_skip_numeric_hex_ids() {
+        let mut db = temp_database("hex");
+        // old hex IDs that happen to be made of digits only
+        insert_case(&db, "1001");
+        insert_case(&db, "1002");
+        insert_case(&db, "1004");
+        insert_case(&db, "3fa91c");
 
-        Ok(Self { conn })
+        assert_eq!(db.next_case_id().unwrap(), "1003");
+        assert_eq!(db.next_case_id().unwrap(), "1005");
     }
 }
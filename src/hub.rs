@@ -1,14 +1,19 @@
 use anyhow::Result;
 use serenity::model::id::ChannelId;
 
+#[derive(Clone)]
 pub struct Hub {
     pub stdout: ChannelId,
+    pub archive: ChannelId,
+    pub escalation: ChannelId,
 }
 
 impl Hub {
     pub fn load(config: &crate::config::Config) -> Result<Self> {
         Ok(Self {
             stdout: ChannelId(config.env.hub_stdout_id),
+            archive: ChannelId(config.env.hub_archive_id),
+            escalation: ChannelId(config.env.hub_escalation_id),
         })
     }
 }
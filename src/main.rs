@@ -1,7 +1,11 @@
+mod case;
 mod commands;
 mod config;
 mod db;
 mod hub;
+mod roles;
+mod scheduler;
+mod transcript;
 mod utils;
 
 extern crate serde_json;
@@ -9,10 +13,12 @@ extern crate serde_json;
 use anyhow::{Error, Result};
 use chrono::{prelude::Utc, DateTime};
 use serenity::{
-    builder::CreateApplicationCommands, model::prelude::ApplicationId,
-    prelude::Context as SerenityContext,
+    builder::CreateApplicationCommands,
+    client::bridge::gateway::ChunkGuildFilter,
+    model::prelude::ApplicationId,
+    prelude::{Context as SerenityContext, GatewayIntents},
 };
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 pub type Context<'a> = poise::Context<'a, State, Error>;
@@ -23,7 +29,8 @@ pub struct State {
     hub: hub::Hub,
     start_time: DateTime<Utc>,
     connected: Mutex<bool>,
-    db: Mutex<db::Database>,
+    db: Arc<Mutex<db::Database>>,
+    roles: roles::RoleIndex,
 }
 
 impl State {
@@ -34,7 +41,8 @@ impl State {
             hub: hub::Hub::load(&config)?,
             start_time: Utc::now(),
             connected: Mutex::new(false),
-            db: Mutex::new(db::Database::load(&config.data_path.dynamic)?),
+            db: Arc::new(Mutex::new(db::Database::load(&config.data_path.dynamic)?)),
+            roles: roles::RoleIndex::new(),
             config,
         })
     }
@@ -63,6 +71,16 @@ async fn listener(
             state.set_connected().await?;
             println!("Bot connected!");
 
+            tokio::spawn(
+                scheduler::Scheduler::new(
+                    ctx.clone(),
+                    state.config.clone(),
+                    state.hub.clone(),
+                    state.db.clone(),
+                )
+                .run(),
+            );
+
             state
                 .hub
                 .stdout
@@ -140,8 +158,42 @@ async fn listener(
                     new_message.to_owned(),
                 )
                 .await?;
+            } else if !new_message.author.bot {
+                commands::support::track_message(state, new_message)?;
             }
         }
+        poise::Event::GuildCreate { guild, .. } => {
+            state.roles.load_members(guild.members.values());
+            // large guilds only send part of their members up front
+            ctx.shard
+                .chunk_guild(guild.id, None, ChunkGuildFilter::None, None);
+        }
+        poise::Event::GuildMembersChunk { chunk } => {
+            state.roles.load_members(chunk.members.values());
+        }
+        poise::Event::GuildMemberAddition { new_member, .. } => {
+            state
+                .roles
+                .update_member(new_member.guild_id, new_member.user.id, &new_member.roles);
+        }
+        poise::Event::GuildMemberUpdate { new, .. } => {
+            state
+                .roles
+                .update_member(new.guild_id, new.user.id, &new.roles);
+        }
+        poise::Event::GuildMemberRemoval { guild_id, user, .. } => {
+            state.roles.remove_member(*guild_id, user.id);
+        }
+        poise::Event::MessageUpdate {
+            old_if_available,
+            event,
+            ..
+        } => {
+            commands::support::track_edit(state, old_if_available.as_ref(), event)?;
+        }
+        poise::Event::InteractionCreate { interaction } => {
+            commands::support::handle_interaction(ctx, state, interaction).await?;
+        }
         _ => {}
     }
 
@@ -184,7 +236,10 @@ async fn main() -> Result<()> {
         init_framework()?,
     );
     framework
-        .start(serenity::client::ClientBuilder::new(env.token))
+        .start(
+            serenity::client::ClientBuilder::new(env.token)
+                .intents(GatewayIntents::non_privileged() | GatewayIntents::GUILD_MEMBERS),
+        )
         .await?;
 
     Ok(())
@@ -0,0 +1,77 @@
+use serenity::model::{
+    guild::Member,
+    id::{GuildId, RoleId, UserId},
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+#[derive(Default)]
+struct Index {
+    members: HashMap<RoleId, HashSet<UserId>>,
+    roles: HashMap<(GuildId, UserId), Vec<RoleId>>,
+}
+
+/// In-memory index of which members have which roles, kept up to date from gateway events so
+/// role lookups never have to page through the member list over HTTP.
+#[derive(Default)]
+pub struct RoleIndex {
+    index: RwLock<Index>,
+}
+
+impl RoleIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a batch of members, e.g. from a guild create or a member chunk.
+    pub fn load_members<'a>(&self, members: impl Iterator<Item = &'a Member>) {
+        for member in members {
+            self.update_member(member.guild_id, member.user.id, &member.roles);
+        }
+    }
+
+    /// Replaces the roles a member has in a guild.
+    pub fn update_member(&self, guild_id: GuildId, user_id: UserId, roles: &[RoleId]) {
+        let mut index = self.index.write().unwrap();
+
+        if let Some(old_roles) = index.roles.remove(&(guild_id, user_id)) {
+            for role in old_roles {
+                if let Some(members) = index.members.get_mut(&role) {
+                    members.remove(&user_id);
+                }
+            }
+        }
+
+        for role in roles {
+            index.members.entry(*role).or_default().insert(user_id);
+        }
+        index.roles.insert((guild_id, user_id), roles.to_vec());
+    }
+
+    pub fn remove_member(&self, guild_id: GuildId, user_id: UserId) {
+        self.update_member(guild_id, user_id, &[]);
+        self.index
+            .write()
+            .unwrap()
+            .roles
+            .remove(&(guild_id, user_id));
+    }
+
+    pub fn members_with(&self, role: RoleId) -> Vec<UserId> {
+        self.index
+            .read()
+            .unwrap()
+            .members
+            .get(&role)
+            .map_or(vec![], |members| members.iter().copied().collect())
+    }
+
+    pub fn has_role(&self, user_id: UserId, role: RoleId) -> bool {
+        self.index
+            .read()
+            .unwrap()
+            .members
+            .get(&role)
+            .map_or(false, |members| members.contains(&user_id))
+    }
+}
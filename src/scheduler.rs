@@ -0,0 +1,238 @@
+use crate::{case::Status, config::Config, db::Database, hub::Hub, transcript, utils};
+use anyhow::Result;
+use chrono::{prelude::Utc, Duration, SecondsFormat};
+use serenity::{model::id::ChannelId, prelude::Context as SerenityContext};
+use std::sync::{Arc, Mutex};
+use tokio::time;
+
+/// How often the scheduler checks for support cases that need attention.
+const TICK: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Background task that runs next to the framework and handles everything that happens to
+/// support cases without a command being used.
+pub struct Scheduler {
+    ctx: SerenityContext,
+    config: Config,
+    hub: Hub,
+    db: Arc<Mutex<Database>>,
+}
+
+impl Scheduler {
+    pub fn new(ctx: SerenityContext, config: Config, hub: Hub, db: Arc<Mutex<Database>>) -> Self {
+        Self {
+            ctx,
+            config,
+            hub,
+            db,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut interval = time::interval(TICK);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(error) = self.check_idle_cases().await {
+                println!("Error in scheduler: {:?}", error);
+            }
+            if let Err(error) = self.check_unanswered_calls().await {
+                println!("Error in scheduler: {:?}", error);
+            }
+        }
+    }
+
+    // ====================================================================================
+    //                                  Inactivity
+    // ====================================================================================
+
+    async fn check_idle_cases(&self) -> Result<()> {
+        let now = Utc::now();
+        let warn_before = (now - Duration::from_std(self.config.env.case_idle_warning)?)
+            .to_rfc3339_opts(SecondsFormat::Millis, true);
+        let close_before = (now - Duration::from_std(self.config.env.case_idle_close)?)
+            .to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        let (idle, expired) = {
+            let db = self.db.lock().unwrap();
+
+            (
+                db.idle_cases(&warn_before)?,
+                db.expired_cases(&close_before)?,
+            )
+        };
+
+        let idle_for =
+            utils::chron::format_duration(Duration::from_std(self.config.env.case_idle_warning)?)?;
+        let closes_in =
+            utils::chron::format_duration(Duration::from_std(self.config.env.case_idle_close)?)?;
+
+        // a failing case is logged and skipped so it can never hold up the ones after it
+        for (case_id, owner_id, thread_id) in idle {
+            // marked first, a thread that cannot be messaged should not be warned every tick
+            if let Err(error) = self.db.lock().unwrap().mark_idle_warned(&case_id) {
+                println!("Unable to mark case-{} as warned: {:?}", case_id, error);
+                continue;
+            }
+
+            if let Err(error) = ChannelId(thread_id)
+                .send_message(&self.ctx.http, |m| {
+                    m.content(format!(
+                        "<@{}>, this support case has had no activity for {}. If nobody replies \
+                        within {} it will be closed automatically.",
+                        owner_id, idle_for, closes_in
+                    ))
+                })
+                .await
+            {
+                println!(
+                    "Unable to warn case-{} about inactivity: {:?}",
+                    case_id, error
+                );
+            }
+        }
+
+        for (case_id, thread_id) in expired {
+            if let Err(error) = self.abandon_case(&case_id, ChannelId(thread_id)).await {
+                println!("Unable to abandon case-{}: {:?}", case_id, error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Closes a support case that stayed idle after its warning. The status is only set once
+    /// the thread is archived, so a case whose thread could not be closed is tried again on the
+    /// next tick instead of staying open while the database says it is abandoned.
+    async fn abandon_case(&self, case_id: &str, thread_id: ChannelId) -> Result<()> {
+        let status = self.db.lock().unwrap().case_status(case_id)?;
+        if !status.can_transition_to(Status::Abandoned) {
+            return Ok(());
+        }
+
+        thread_id
+            .send_message(&self.ctx.http, |m| {
+                m.content(
+                    "This support case has been closed due to inactivity and can only be \
+                    re-opened by a staff member.",
+                )
+            })
+            .await?;
+
+        if let Err(error) = transcript::export(
+            &self.ctx.http,
+            &self.config,
+            &self.hub,
+            &self.db,
+            case_id,
+            thread_id,
+        )
+        .await
+        {
+            println!(
+                "Unable to export the transcript of case-{}: {:?}",
+                case_id, error
+            );
+        }
+
+        thread_id
+            .edit_thread(&self.ctx.http, |t| {
+                t.archived(true);
+                t.locked(true)
+            })
+            .await?;
+
+        self.db.lock().unwrap().set_status(
+            case_id,
+            Status::Abandoned,
+            None,
+            Some("closed after inactivity"),
+        )?;
+
+        Ok(())
+    }
+
+    // ====================================================================================
+    //                                  Escalation
+    // ====================================================================================
+
+    async fn check_unanswered_calls(&self) -> Result<()> {
+        let delay = Duration::from_std(self.config.env.case_escalation_delay)?;
+        let before = (Utc::now() - delay).to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        let unanswered = self.db.lock().unwrap().unanswered_calls(&before)?;
+
+        for (case_id, thread_id) in unanswered {
+            if let Err(error) = self.escalate_case(&case_id, thread_id, delay).await {
+                println!("Unable to escalate case-{}: {:?}", case_id, error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Posts a support case nobody responded to in the hub escalation channel.
+    async fn escalate_case(&self, case_id: &str, thread_id: u64, delay: Duration) -> Result<()> {
+        let (case, calls) = {
+            let db = self.db.lock().unwrap();
+
+            (db.case_by_thread(thread_id)?, db.case_calls(case_id)?)
+        };
+        let case = match case {
+            Some(case) => case,
+            None => return Ok(()),
+        };
+        // a thread that cannot be fetched still gets escalated, just without a jump link
+        let guild_id = match ChannelId(thread_id).to_channel(&self.ctx.http).await {
+            Ok(channel) => channel.guild().map(|thread| thread.guild_id),
+            Err(_) => None,
+        };
+
+        let waiting = utils::chron::format_duration(delay)?;
+        let jump = match guild_id {
+            Some(guild_id) => format!(
+                "[Jump to Case](https://discord.com/channels/{}/{})",
+                guild_id, thread_id
+            ),
+            None => format!("<#{}>", thread_id),
+        };
+
+        self.hub
+            .escalation
+            .send_message(&self.ctx.http, |m| {
+                m.embed(|embed| {
+                    embed.title(format!("Escalated: case-{}", case.id));
+                    embed.description(format!(
+                        "The helpers were called to this support case, but nobody has \
+                        responded for {}.",
+                        waiting
+                    ));
+                    embed.color(self.config.env.default_embed_color);
+                    embed.field("Title", &case.title, false);
+                    embed.field("Owner", format!("<@{}>", case.owner_id), true);
+                    embed.field("Language", &case.language, true);
+                    embed.field("Status", case.status, true);
+                    embed.field(
+                        "Claimed By",
+                        case.claimed_by
+                            .map_or("Nobody".to_string(), |c| format!("<@{}>", c)),
+                        true,
+                    );
+                    embed.field("Calls", calls.len(), true);
+                    embed.field(
+                        "Opened",
+                        format!("<t:{}:R>", case.created_at.timestamp()),
+                        true,
+                    );
+                    embed.field("Context", jump, false);
+
+                    embed
+                })
+            })
+            .await?;
+
+        self.db.lock().unwrap().mark_escalated(case_id)?;
+
+        Ok(())
+    }
+}
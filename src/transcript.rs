@@ -0,0 +1,305 @@
+use crate::{config::Config, db::Database, hub::Hub, utils};
+use anyhow::Result;
+use chrono::prelude::Utc;
+use serenity::{
+    http::Http,
+    model::{
+        channel::{Message, MessageType},
+        id::{ChannelId, MessageId},
+    },
+};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+/// A single message of a support case as it appears in a transcript.
+struct Entry {
+    author: String,
+    timestamp: String,
+    content: String,
+    embeds: Vec<String>,
+    attachments: Vec<(String, String)>,
+    edits: Vec<(String, String)>,
+}
+
+// ========================================================================================
+//                                  Export
+// ========================================================================================
+
+/// Writes the Markdown and HTML transcripts of a support case, posts them to the hub archive
+/// channel and stores where they were written on the case. Returns the transcript directory.
+pub async fn export(
+    http: &Http,
+    config: &Config,
+    hub: &Hub,
+    db: &Mutex<Database>,
+    case_id: &str,
+    thread_id: ChannelId,
+) -> Result<String> {
+    let messages =
+        fetch_messages(http, ChannelId(config.env.support_channel_id), thread_id).await?;
+
+    let (title, language, mut edits) = {
+        let db = db.lock().unwrap();
+        let (title, language): (String, String) = db.conn.query_row(
+            "SELECT title, language FROM support WHERE id = ?",
+            [case_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        let mut edits: HashMap<u64, Vec<(String, String)>> = HashMap::new();
+
+        for (message_id, content, edited_at) in db.case_edits(case_id)? {
+            edits
+                .entry(message_id)
+                .or_default()
+                .push((edited_at, content));
+        }
+
+        (title, language, edits)
+    };
+
+    let entries: Vec<Entry> = messages
+        .iter()
+        .map(|m| {
+            // earlier versions only, the current content is already part of the entry
+            let edits = edits
+                .remove(m.id.as_u64())
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, content)| content != &m.content)
+                .collect();
+
+            entry(m, edits)
+        })
+        .collect();
+
+    let path = format!("{}/transcripts/{}", config.data_path.dynamic, case_id);
+    let markdown_path = format!("{}/transcript.md", path);
+    let html_path = format!("{}/transcript.html", path);
+
+    fs::create_dir_all(&path)?;
+    fs::write(
+        &markdown_path,
+        render_markdown(case_id, &title, &language, &entries)?,
+    )?;
+    fs::write(
+        &html_path,
+        render_html(case_id, &title, &language, &entries)?,
+    )?;
+
+    hub.archive
+        .send_files(
+            http,
+            vec![markdown_path.as_str(), html_path.as_str()],
+            |m| {
+                m.content(format!(
+                    "Transcript of support case case-{} ({}) in <#{}>.",
+                    case_id, title, thread_id
+                ))
+            },
+        )
+        .await?;
+
+    db.lock().unwrap().conn.execute(
+        "UPDATE support SET transcript_path = ? WHERE id = ?",
+        [path.as_str(), case_id],
+    )?;
+
+    Ok(path)
+}
+
+/// Fetches every message of a thread, oldest first, starting with the message in the support
+/// channel the thread was started on.
+async fn fetch_messages(
+    http: &Http,
+    support_channel: ChannelId,
+    thread_id: ChannelId,
+) -> Result<Vec<Message>> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut after = MessageId(0);
+
+    loop {
+        let mut batch = thread_id
+            .messages(http, |r| r.after(after).limit(100))
+            .await?;
+        let done = batch.len() < 100;
+
+        batch.sort_by_key(|m| m.id);
+        if let Some(last) = batch.last() {
+            after = last.id;
+        }
+        messages.append(&mut batch);
+
+        if done {
+            break;
+        }
+    }
+
+    // the thread only holds an empty starter message pointing to the message it was started
+    // on, which shares its ID with the thread
+    let starter = messages
+        .iter()
+        .position(|m| m.kind == MessageType::ThreadStarterMessage)
+        .map(|i| messages.remove(i));
+    let opening = match starter.and_then(|m| m.referenced_message) {
+        Some(opening) => Some(*opening),
+        None => support_channel
+            .message(http, MessageId(*thread_id.as_u64()))
+            .await
+            .ok(),
+    };
+    if let Some(opening) = opening {
+        messages.insert(0, opening);
+    }
+
+    Ok(messages)
+}
+
+fn entry(message: &Message, edits: Vec<(String, String)>) -> Entry {
+    Entry {
+        author: format!(
+            "{}#{:04}",
+            message.author.name, message.author.discriminator
+        ),
+        timestamp: message.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+        content: message.content.to_owned(),
+        embeds: message
+            .embeds
+            .iter()
+            .map(|e| {
+                vec![e.title.to_owned(), e.description.to_owned()]
+                    .into_iter()
+                    .flatten()
+                    .chain(e.fields.iter().map(|f| format!("{}:\n{}", f.name, f.value)))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            })
+            .filter(|e| !e.is_empty())
+            .collect(),
+        attachments: message
+            .attachments
+            .iter()
+            .map(|a| (a.filename.to_owned(), a.url.to_owned()))
+            .collect(),
+        edits,
+    }
+}
+
+// ========================================================================================
+//                                  Rendering
+// ========================================================================================
+
+fn render_markdown(
+    case_id: &str,
+    title: &str,
+    language: &str,
+    entries: &[Entry],
+) -> Result<String> {
+    let mut out = format!(
+        "# Support case case-{}: {}\n\nLanguage: {}  \nExported: {} UTC\n",
+        case_id,
+        title,
+        language,
+        Utc::now().format("%Y-%m-%d %H:%M:%S")
+    );
+
+    for entry in entries {
+        out += &format!(
+            "\n---\n\n**{}** at {} UTC\n\n",
+            entry.author, entry.timestamp
+        );
+
+        if !entry.content.is_empty() {
+            out += &format!("{}\n", entry.content);
+        }
+        for embed in &entry.embeds {
+            for line in embed.lines() {
+                out += &format!("> {}\n", line);
+            }
+        }
+        for (filename, url) in &entry.attachments {
+            out += &format!("\nAttachment: [{}]({})\n", filename, url);
+        }
+        for (edited_at, content) in &entry.edits {
+            out += &format!("\n*Earlier version ({}):*\n", edited_at);
+            for line in content.lines() {
+                out += &format!("> {}\n", line);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn render_html(case_id: &str, title: &str, language: &str, entries: &[Entry]) -> Result<String> {
+    let mut body = String::new();
+
+    for entry in entries {
+        body += "<div class=\"message\">\n";
+        body += &format!(
+            "<div class=\"meta\"><span class=\"author\">{}</span> <span class=\"time\">{} UTC</span></div>\n",
+            utils::string::escape_html(&entry.author)?,
+            entry.timestamp
+        );
+
+        if !entry.content.is_empty() {
+            body += &format!(
+                "<div class=\"content\">{}</div>\n",
+                utils::string::escape_html(&entry.content)?
+            );
+        }
+        for embed in &entry.embeds {
+            body += &format!(
+                "<div class=\"embed\">{}</div>\n",
+                utils::string::escape_html(embed)?
+            );
+        }
+        for (filename, url) in &entry.attachments {
+            body += &format!(
+                "<div class=\"attachment\">Attachment: <a href=\"{}\">{}</a></div>\n",
+                utils::string::escape_html(url)?,
+                utils::string::escape_html(filename)?
+            );
+        }
+        for (edited_at, content) in &entry.edits {
+            body += &format!(
+                "<div class=\"edit\"><span class=\"time\">Earlier version ({})</span>\n{}</div>\n",
+                utils::string::escape_html(edited_at)?,
+                utils::string::escape_html(content)?
+            );
+        }
+
+        body += "</div>\n";
+    }
+
+    Ok(format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>case-{id}: {title}</title>
+<style>
+body {{ font-family: sans-serif; background: #36393f; color: #dcddde; margin: 2em; }}
+h1 {{ color: #ffffff; }}
+.message {{ border-top: 1px solid #4f545c; padding: 0.75em 0; }}
+.author {{ font-weight: bold; color: #ffffff; }}
+.time {{ color: #72767d; font-size: 0.85em; }}
+.content, .embed, .edit {{ white-space: pre-wrap; margin-top: 0.25em; }}
+.embed {{ border-left: 4px solid #4f545c; padding-left: 0.5em; }}
+.edit {{ border-left: 4px solid #faa61a; padding-left: 0.5em; color: #b9bbbe; }}
+a {{ color: #00b0f4; }}
+</style>
+</head>
+<body>
+<h1>case-{id}: {title}</h1>
+<p>Language: {language}<br>Exported: {exported} UTC</p>
+{body}</body>
+</html>
+",
+        id = utils::string::escape_html(case_id)?,
+        title = utils::string::escape_html(title)?,
+        language = utils::string::escape_html(language)?,
+        exported = Utc::now().format("%Y-%m-%d %H:%M:%S"),
+        body = body
+    ))
+}
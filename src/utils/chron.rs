@@ -2,7 +2,10 @@ use anyhow::Result;
 use chrono::{prelude::Utc, DateTime, Duration};
 
 pub fn time_diff(start: DateTime<Utc>, end: DateTime<Utc>) -> Result<String> {
-    let duration: Duration = end - start;
+    format_duration(end - start)
+}
+
+pub fn format_duration(duration: Duration) -> Result<String> {
     let mut diff: Vec<String> = vec![];
 
     let weeks = duration.num_weeks();
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use serenity::model::interactions::{
+    message_component::{ActionRow, ActionRowComponent},
+    modal::ModalSubmitInteraction,
+};
+use std::collections::HashMap;
+
+/// Collects the values of all text inputs of a submitted modal, keyed by their custom ID.
+pub fn modal_values(modal: &ModalSubmitInteraction) -> Result<HashMap<String, String>> {
+    let rows: &Vec<ActionRow> = &modal.data.components;
+    let mut values: HashMap<String, String> = HashMap::new();
+
+    for row in rows {
+        for component in &row.components {
+            if let ActionRowComponent::InputText(input) = component {
+                values.insert(input.custom_id.to_owned(), input.value.trim().to_string());
+            }
+        }
+    }
+
+    Ok(values)
+}
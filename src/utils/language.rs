@@ -0,0 +1,266 @@
+use anyhow::Result;
+use regex::Regex;
+use std::sync::OnceLock;
+
+struct Language {
+    name: &'static str,
+    /// Tags used after the opening fence of a code block, e.g. ```rs
+    tags: &'static [&'static str],
+    extensions: &'static [&'static str],
+    keywords: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "Rust",
+        tags: &["rust", "rs"],
+        extensions: &["rs"],
+        keywords: &[
+            "fn main",
+            "let mut",
+            "impl ",
+            "println!",
+            "use std::",
+            "cargo",
+            "&mut self",
+        ],
+    },
+    Language {
+        name: "Python",
+        tags: &["python", "py", "python3"],
+        extensions: &["py"],
+        keywords: &[
+            "def ",
+            "elif ",
+            "print(",
+            "self.",
+            "import ",
+            "pip install",
+            "__init__",
+        ],
+    },
+    Language {
+        name: "JavaScript",
+        tags: &["javascript", "js", "jsx", "node"],
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        keywords: &[
+            "console.log",
+            "const ",
+            "require(",
+            "document.",
+            "npm ",
+            "=> {",
+            "function ",
+        ],
+    },
+    Language {
+        name: "TypeScript",
+        tags: &["typescript", "ts", "tsx"],
+        extensions: &["ts", "tsx"],
+        keywords: &["interface ", ": string", ": number", "tsconfig", "tsc "],
+    },
+    Language {
+        name: "Java",
+        tags: &["java"],
+        extensions: &["java"],
+        keywords: &[
+            "public static void main",
+            "System.out.println",
+            "public class",
+            "import java.",
+        ],
+    },
+    Language {
+        name: "C#",
+        tags: &["cs", "csharp", "c#"],
+        extensions: &["cs"],
+        keywords: &[
+            "using System",
+            "Console.WriteLine",
+            "namespace ",
+            "public void",
+        ],
+    },
+    Language {
+        name: "C++",
+        tags: &["cpp", "c++", "cc", "hpp"],
+        extensions: &["cpp", "cc", "cxx", "hpp"],
+        keywords: &[
+            "std::cout",
+            "#include <iostream>",
+            "std::vector",
+            "std::string",
+        ],
+    },
+    Language {
+        name: "C",
+        tags: &["c", "h"],
+        extensions: &["c", "h"],
+        keywords: &["#include <stdio.h>", "printf(", "malloc(", "int main("],
+    },
+    Language {
+        name: "Go",
+        tags: &["go", "golang"],
+        extensions: &["go"],
+        keywords: &["package main", "func main", "fmt.Println", ":= ", "go mod"],
+    },
+    Language {
+        name: "PHP",
+        tags: &["php"],
+        extensions: &["php"],
+        keywords: &["<?php", "echo ", "$_GET", "$_POST"],
+    },
+    Language {
+        name: "Ruby",
+        tags: &["ruby", "rb"],
+        extensions: &["rb"],
+        keywords: &["puts ", "require '", "attr_accessor", "gem install"],
+    },
+    Language {
+        name: "Kotlin",
+        tags: &["kotlin", "kt"],
+        extensions: &["kt", "kts"],
+        keywords: &["fun main", "val ", "println(", "data class"],
+    },
+    Language {
+        name: "Swift",
+        tags: &["swift"],
+        extensions: &["swift"],
+        keywords: &["import SwiftUI", "import UIKit", "guard let", "func "],
+    },
+    Language {
+        name: "HTML",
+        tags: &["html", "htm"],
+        extensions: &["html", "htm"],
+        keywords: &["<!DOCTYPE html", "<div", "<body", "<head"],
+    },
+    Language {
+        name: "CSS",
+        tags: &["css", "scss", "sass"],
+        extensions: &["css", "scss", "sass"],
+        keywords: &["display: flex", "margin:", "padding:", "font-size:"],
+    },
+    Language {
+        name: "SQL",
+        tags: &["sql"],
+        extensions: &["sql"],
+        keywords: &["SELECT ", "INSERT INTO", "CREATE TABLE", "WHERE "],
+    },
+    Language {
+        name: "Shell",
+        tags: &["bash", "sh", "shell", "zsh"],
+        extensions: &["sh", "bash", "zsh"],
+        keywords: &["#!/bin/bash", "#!/bin/sh", "sudo ", "chmod "],
+    },
+];
+
+/// Keyword hits needed before a language is guessed from the message text alone.
+const MIN_KEYWORD_SCORE: usize = 2;
+
+/// Matches the tag after the opening fence of a code block, compiled once on first use.
+static FENCE: OnceLock<Regex> = OnceLock::new();
+
+/// Guesses the programming language a message is about. Code block tags are trusted the most,
+/// then attachment extensions and finally keywords found in the text.
+pub fn detect(content: &str, filenames: &[&str]) -> Result<Option<&'static str>> {
+    let fence = match FENCE.get() {
+        Some(fence) => fence,
+        None => {
+            let fence = Regex::new(r"```([A-Za-z0-9+#]+)")?;
+            FENCE.get_or_init(|| fence)
+        }
+    };
+
+    for tag in fence.captures_iter(content) {
+        let tag = tag[1].to_lowercase();
+
+        if let Some(language) = LANGUAGES.iter().find(|l| l.tags.contains(&tag.as_str())) {
+            return Ok(Some(language.name));
+        }
+    }
+
+    for filename in filenames {
+        if let Some((_, extension)) = filename.rsplit_once('.') {
+            let extension = extension.to_lowercase();
+
+            if let Some(language) = LANGUAGES
+                .iter()
+                .find(|l| l.extensions.contains(&extension.as_str()))
+            {
+                return Ok(Some(language.name));
+            }
+        }
+    }
+
+    let scores: Vec<(&'static str, usize)> = LANGUAGES
+        .iter()
+        .map(|l| {
+            let score = l.keywords.iter().filter(|k| content.contains(*k)).count();
+            (l.name, score)
+        })
+        .collect();
+    let top = scores.iter().map(|(_, score)| *score).max().unwrap_or(0);
+
+    // languages sharing the top score cannot be told apart, so none of them is guessed
+    let mut best = scores.iter().filter(|(_, score)| *score == top);
+    match (best.next(), best.next()) {
+        (Some((name, score)), None) if *score >= MIN_KEYWORD_SCORE => Ok(Some(name)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::detect;
+
+    #[test]
+    fn fence_tag_wins() {
+        let content = "```py\nfn main() { let mut x = 1; println!(\"{}\", x); }\n```";
+        assert_eq!(detect(content, &[]).unwrap(), Some("Python"));
+    }
+
+    #[test]
+    fn fence_tag_is_case_insensitive() {
+        assert_eq!(detect("```Rust\nx\n```", &[]).unwrap(), Some("Rust"));
+        assert_eq!(detect("```C#\nx\n```", &[]).unwrap(), Some("C#"));
+    }
+
+    #[test]
+    fn unknown_fence_tag_falls_through() {
+        assert_eq!(
+            detect("```text\nhello\n```", &["main.go"]).unwrap(),
+            Some("Go")
+        );
+    }
+
+    #[test]
+    fn extension_is_used() {
+        assert_eq!(detect("it crashes", &["Main.JAVA"]).unwrap(), Some("Java"));
+        assert_eq!(
+            detect("it crashes", &["notes", "app.tsx"]).unwrap(),
+            Some("TypeScript")
+        );
+    }
+
+    #[test]
+    fn keywords_are_used() {
+        let go = "package main\n\nfunc main() {\n\tfmt.Println(\"hi\")\n}";
+        assert_eq!(detect(go, &[]).unwrap(), Some("Go"));
+
+        let swift = "import SwiftUI\nfunc greet() { guard let name = name else { return } }";
+        assert_eq!(detect(swift, &[]).unwrap(), Some("Swift"));
+    }
+
+    #[test]
+    fn single_keyword_is_not_enough() {
+        // "func main" alone hits both Go and Swift once
+        assert_eq!(detect("my func main does not run", &[]).unwrap(), None);
+        assert_eq!(detect("where do I import this from?", &[]).unwrap(), None);
+    }
+
+    #[test]
+    fn tied_keywords_guess_nothing() {
+        let content = "let mut x = 1; println!(\"{}\", x);\ndef f(): print(x)";
+        assert_eq!(detect(content, &[]).unwrap(), None);
+    }
+}
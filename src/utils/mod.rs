@@ -1,2 +1,6 @@
 pub mod chron;
+pub mod interaction;
+pub mod language;
+pub mod paginate;
+pub mod stats;
 pub mod string;
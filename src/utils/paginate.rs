@@ -0,0 +1,157 @@
+use crate::Context;
+use anyhow::Result;
+use chrono::prelude::Utc;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::collector::component_interaction_collector::CollectComponentInteraction;
+use serenity::model::{
+    interactions::message_component::ButtonStyle, prelude::InteractionResponseType,
+};
+use uuid::Uuid;
+
+/// A single page of a paginated embed.
+pub struct Page {
+    pub description: Option<String>,
+    pub fields: Vec<(String, String, bool)>,
+}
+
+/// Sends an embed that can be paged through with buttons until the interaction times out.
+pub async fn send(ctx: Context<'_>, title: &str, pages: Vec<Page>) -> Result<()> {
+    if pages.is_empty() {
+        return Ok(());
+    }
+
+    let uuid_pages = Uuid::new_v4();
+    let total = pages.len();
+    let mut index: usize = 0;
+
+    let reply = poise::send_reply(ctx, |m| {
+        m.embed(|embed| page_embed(embed, ctx, title, &pages[index], index, total));
+        if total > 1 {
+            m.components(|c| page_buttons(c, &uuid_pages, index, total));
+        }
+        m
+    })
+    .await?
+    .message()
+    .await?;
+
+    if total == 1 {
+        return Ok(());
+    }
+
+    let reply_channel_id = reply.channel_id;
+    let reply_id = reply.id;
+
+    loop {
+        let mov_uuid_pages = uuid_pages.to_string();
+        let mci = CollectComponentInteraction::new(ctx.discord())
+            .author_id(ctx.author().id)
+            .channel_id(ctx.channel_id())
+            .timeout(ctx.data().config.env.default_interaction_timeout)
+            .filter(move |mci| mci.data.custom_id.starts_with(&mov_uuid_pages))
+            .await;
+
+        if let Some(mci) = mci {
+            let mut msg = mci.message.clone();
+
+            if mci.data.custom_id.ends_with("-prev") {
+                index = index.saturating_sub(1);
+            } else if mci.data.custom_id.ends_with("-next") {
+                index = (index + 1).min(total - 1);
+            }
+
+            msg.edit(ctx.discord(), |m| {
+                m.embed(|embed| page_embed(embed, ctx, title, &pages[index], index, total));
+                m.components(|c| page_buttons(c, &uuid_pages, index, total))
+            })
+            .await?;
+
+            mci.create_interaction_response(ctx.discord(), |ir| {
+                ir.kind(InteractionResponseType::DeferredUpdateMessage)
+            })
+            .await?;
+        } else {
+            let mut msg = ctx
+                .discord()
+                .http
+                .get_message(*reply_channel_id.as_u64(), *reply_id.as_u64())
+                .await?;
+
+            msg.edit(ctx.discord(), |m| {
+                m.components(|c| c);
+                m.embed(|embed| {
+                    page_embed(embed, ctx, title, &pages[index], index, total);
+                    embed.footer(|f| {
+                        f.text(format!(
+                            "Page {}/{} | Interaction timed out at {} UTC",
+                            index + 1,
+                            total,
+                            Utc::now().format("%Y-%m-%d %H:%M:%S")
+                        ));
+                        f
+                    });
+
+                    embed
+                })
+            })
+            .await?;
+
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn page_embed<'a>(
+    embed: &'a mut CreateEmbed,
+    ctx: Context<'_>,
+    title: &str,
+    page: &Page,
+    index: usize,
+    total: usize,
+) -> &'a mut CreateEmbed {
+    embed.title(title);
+    embed.color(ctx.data().config.env.default_embed_color);
+    if let Some(description) = &page.description {
+        embed.description(description);
+    }
+    for (name, value, inline) in &page.fields {
+        embed.field(name, value, *inline);
+    }
+    embed.footer(|f| {
+        f.text(format!("Page {}/{}", index + 1, total));
+        f
+    });
+
+    embed
+}
+
+fn page_buttons<'a>(
+    components: &'a mut CreateComponents,
+    uuid_pages: &Uuid,
+    index: usize,
+    total: usize,
+) -> &'a mut CreateComponents {
+    components.create_action_row(|ar| {
+        ar.create_button(|b| {
+            b.style(ButtonStyle::Secondary);
+            b.label("Previous");
+            b.custom_id(format!("{}-prev", uuid_pages));
+            b.disabled(index == 0);
+
+            b
+        });
+        ar.create_button(|b| {
+            b.style(ButtonStyle::Secondary);
+            b.label("Next");
+            b.custom_id(format!("{}-next", uuid_pages));
+            b.disabled(index + 1 >= total);
+
+            b
+        });
+        ar
+    });
+
+    components
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+
+/// Nearest-rank percentile of a set of values, `None` if there are no values.
+pub fn percentile(values: &[i64], percentile: f64) -> Result<Option<i64>> {
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+
+    Ok(Some(sorted[rank.clamp(1, sorted.len()) - 1]))
+}
+
+pub fn median(values: &[i64]) -> Result<Option<i64>> {
+    percentile(values, 50.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{median, percentile};
+
+    #[test]
+    fn empty_values_have_no_percentile() {
+        assert_eq!(percentile(&[], 90.0).unwrap(), None);
+        assert_eq!(median(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn nearest_rank() {
+        let values = [15, 20, 35, 40, 50];
+
+        assert_eq!(percentile(&values, 5.0).unwrap(), Some(15));
+        assert_eq!(percentile(&values, 30.0).unwrap(), Some(20));
+        assert_eq!(percentile(&values, 40.0).unwrap(), Some(20));
+        assert_eq!(percentile(&values, 50.0).unwrap(), Some(35));
+        assert_eq!(percentile(&values, 100.0).unwrap(), Some(50));
+    }
+
+    #[test]
+    fn unsorted_values_and_bounds() {
+        let values = [40, 15, 50, 35, 20];
+
+        assert_eq!(median(&values).unwrap(), Some(35));
+        assert_eq!(percentile(&values, 0.0).unwrap(), Some(15));
+        assert_eq!(percentile(&values, 150.0).unwrap(), Some(50));
+        assert_eq!(percentile(&[7], 90.0).unwrap(), Some(7));
+    }
+}
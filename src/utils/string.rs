@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::collections::HashMap;
 
 pub fn into_titlecase(string: &mut str) -> Result<String> {
     if let Some(c) = string.get_mut(0..1) {
@@ -7,3 +8,92 @@ pub fn into_titlecase(string: &mut str) -> Result<String> {
 
     Ok(string.to_string())
 }
+
+/// Shortens a string to at most `length` characters, marking the cut with an ellipsis.
+pub fn truncate(string: &str, length: usize) -> Result<String> {
+    if string.chars().count() <= length {
+        return Ok(string.to_string());
+    }
+
+    Ok(string.chars().take(length - 1).collect::<String>() + "…")
+}
+
+/// Escapes the characters that have a special meaning in HTML.
+pub fn escape_html(string: &str) -> Result<String> {
+    Ok(string
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;"))
+}
+
+/// Cosine similarity of the words used in two texts, from 0 for nothing in common to 1 for the
+/// same words. Words shorter than three characters say little about a text and are ignored.
+pub fn similarity(a: &str, b: &str) -> Result<f64> {
+    let (a, b) = (word_counts(a), word_counts(b));
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(word, n)| b.get(word).map(|m| (n * m) as f64))
+        .sum();
+    let norm =
+        |counts: &HashMap<String, u32>| counts.values().map(|n| (n * n) as f64).sum::<f64>().sqrt();
+    let norms = norm(&a) * norm(&b);
+
+    if norms == 0.0 {
+        return Ok(0.0);
+    }
+
+    Ok(dot / norms)
+}
+
+fn word_counts(text: &str) -> HashMap<String, u32> {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+    {
+        *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::similarity;
+
+    #[test]
+    fn identical_texts_are_fully_similar() {
+        let text = "Borrowed value does not live long enough";
+        assert!((similarity(text, text).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn case_and_punctuation_are_ignored() {
+        let score = similarity("Borrow checker error!", "borrow, CHECKER... error").unwrap();
+        assert!((score - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unrelated_texts_are_not_similar() {
+        assert_eq!(
+            similarity("segfault in malloc", "css flexbox layout").unwrap(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn short_words_are_ignored() {
+        assert_eq!(similarity("is it an ok", "is it an ok").unwrap(), 0.0);
+        assert_eq!(similarity("", "anything").unwrap(), 0.0);
+    }
+
+    #[test]
+    fn partial_overlap() {
+        // one shared word out of two on each side
+        let score = similarity("tokio runtime", "tokio panic").unwrap();
+        assert!((score - 0.5).abs() < 1e-9);
+    }
+}